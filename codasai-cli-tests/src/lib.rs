@@ -12,6 +12,16 @@ pub use wild::WildStr;
 const CODASAI_CLI: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/debug/codasai-cli");
 const TMP_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/debug/tmp");
 
+/// Makes the commits reproducible so their ids can be snapshotted.
+const GIT_ENV: &[(&str, &str)] = &[
+    ("GIT_AUTHOR_NAME", "Codasai"),
+    ("GIT_AUTHOR_EMAIL", "codasai@example.com"),
+    ("GIT_AUTHOR_DATE", "2021-01-01T00:00:00Z"),
+    ("GIT_COMMITTER_NAME", "Codasai"),
+    ("GIT_COMMITTER_EMAIL", "codasai@example.com"),
+    ("GIT_COMMITTER_DATE", "2021-01-01T00:00:00Z"),
+];
+
 pub struct ProjectOutput<'a> {
     stdout: String,
     stderr: String,
@@ -56,11 +66,31 @@ impl Project {
         }
     }
 
+    pub fn write_file(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) {
+        let path = self.cwd.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("create parent directories");
+        std::fs::write(path, contents).expect("write file correctly");
+    }
+
+    pub fn remove_file(&self, path: impl AsRef<Path>) {
+        std::fs::remove_file(self.cwd.join(path)).expect("remove file correctly");
+    }
+
     pub fn run(&self, cmd: &str, args: &[&str]) -> ProjectOutput {
+        self.run_with_env(cmd, args, &[])
+    }
+
+    /// Like [run](Project::run) but with more environment variables, which can
+    /// override the git ones.
+    pub fn run_with_env(
+        &self, cmd: &str, args: &[&str], env: &[(&str, &str)],
+    ) -> ProjectOutput<'_> {
         let process = Command::new(CODASAI_CLI)
             .arg(cmd)
             .args(args)
             .current_dir(&self.cwd)
+            .envs(GIT_ENV.iter().copied())
+            .envs(env.iter().copied())
             .stdout(Stdio::piped())
             .stdin(Stdio::piped())
             .stderr(Stdio::piped())
@@ -139,7 +169,7 @@ fn serialize_tree(dir: &Temp) -> String {
 fn sort_tree_entries(l: &Path, r: &Path) -> Ordering {
     if l.is_dir() && r.is_file() {
         Ordering::Less
-    } else if r.is_file() && l.is_dir() {
+    } else if l.is_file() && r.is_dir() {
         Ordering::Greater
     } else {
         l.cmp(r)
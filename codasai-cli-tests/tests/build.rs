@@ -38,6 +38,9 @@ _pages/
           "directories": {},
           "files": {}
         },
+        "id": "introduction",
+        "commit": "a5b7751a369179285adf5bf93ef7ab4d818a4303",
+        "title": "Introduction",
         "page": "<h1>Introduction</h1>\
 "
       }
@@ -47,3 +50,906 @@ _pages/
 "#
     );
 }
+
+#[test]
+fn build_drafts() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    // Without a page, there is nothing to build
+    let output = project.run("build", &["--drafts"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        "
+Error: you have not saved any page
+
+"
+    );
+
+    project.run("page", &["new", "Introduction"]);
+    let output = project.run("build", &["--drafts"]);
+
+    k9::snapshot!(output.stdout(), "");
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.contents(".codasai/out/guide.json"),
+        r#"
+{
+  "name": "Simple guide",
+  "vfs": {
+    "files": [],
+    "snapshots": [
+      {
+        "root": {
+          "directories": {},
+          "files": {}
+        },
+        "id": "introduction",
+        "title": "Introduction",
+        "page": "<h1>Introduction</h1>\
+",
+        "draft": true
+      }
+    ]
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn build_highlights() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.write_file(
+        ".codasai/guide.toml",
+        "title = \"Simple guide\"\n\n[syntaxes]\n\"*.txt\" = \"Rust\"\n",
+    );
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {\n    // Hello\n}\n");
+    project.write_file("script", "#!/bin/sh\necho hello\n");
+    project.write_file("notes.txt", "let x = 1;\n");
+    project.write_file("plain", "hello\n");
+    project.run("page", &["save", "-m", "\"Page: Introduction\""]);
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.contents(".codasai/out/guide.json"),
+        r##"
+{
+  "name": "Simple guide",
+  "vfs": {
+    "files": [
+      {
+        "content": "fn main() {\
+    // Hello\
+}\
+",
+        "highlights": [
+          [
+            [
+              2,
+              "storage"
+            ],
+            [
+              1,
+              ""
+            ],
+            [
+              4,
+              "entity"
+            ]
+          ],
+          [
+            [
+              4,
+              ""
+            ],
+            [
+              8,
+              "comment"
+            ]
+          ]
+        ]
+      },
+      {
+        "content": "let x = 1;\
+",
+        "highlights": [
+          [
+            [
+              3,
+              "storage"
+            ],
+            [
+              3,
+              ""
+            ],
+            [
+              1,
+              "keyword"
+            ],
+            [
+              1,
+              ""
+            ],
+            [
+              1,
+              "constant"
+            ]
+          ]
+        ]
+      },
+      {
+        "content": "hello\
+"
+      },
+      {
+        "content": "#!/bin/sh\
+echo hello\
+",
+        "highlights": [
+          [
+            [
+              9,
+              "comment"
+            ]
+          ],
+          [
+            [
+              4,
+              "support"
+            ]
+          ]
+        ]
+      }
+    ],
+    "snapshots": [
+      {
+        "root": {
+          "directories": {},
+          "files": {
+            "main.rs": 0,
+            "notes.txt": 1,
+            "plain": 2,
+            "script": 3
+          }
+        },
+        "id": "introduction",
+        "commit": "07fae2cdd93d133ac4f4154711110cf6292c727d",
+        "title": "Introduction",
+        "page": "<h1>Introduction</h1>\
+",
+        "changes": [
+          {
+            "kind": "added",
+            "path": "main.rs"
+          },
+          {
+            "kind": "added",
+            "path": "notes.txt"
+          },
+          {
+            "kind": "added",
+            "path": "plain"
+          },
+          {
+            "kind": "added",
+            "path": "script"
+          }
+        ]
+      }
+    ]
+  }
+}
+"##
+    );
+}
+
+#[test]
+fn build_highlight_overrides() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.write_file(
+        ".codasai/guide.toml",
+        "title = \"Simple guide\"\n\n[syntaxes]\n\"*.h\" = \"C\"\n\"include/foo.h\" = \"C++\"\n\"*.xyz\" = \"Nope\"\n",
+    );
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("include/foo.h", "class A;\n");
+    project.write_file("src/foo.h", "class A;\n");
+    project.run("page", &["save", "-m", "\"Page: Introduction\""]);
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+warning: unknown syntax "Nope" for "*.xyz" in guide.toml, so its syntax is detected instead
+
+"#
+    );
+
+    k9::snapshot!(
+        output.contents(".codasai/out/guide.json"),
+        r#"
+{
+  "name": "Simple guide",
+  "vfs": {
+    "files": [
+      {
+        "content": "class A;\
+",
+        "highlights": [
+          [
+            [
+              5,
+              "storage"
+            ],
+            [
+              1,
+              ""
+            ],
+            [
+              1,
+              "entity"
+            ]
+          ]
+        ]
+      },
+      {
+        "content": "class A;\
+"
+      }
+    ],
+    "snapshots": [
+      {
+        "root": {
+          "directories": {
+            "include": {
+              "directories": {},
+              "files": {
+                "foo.h": 0
+              }
+            },
+            "src": {
+              "directories": {},
+              "files": {
+                "foo.h": 1
+              }
+            }
+          },
+          "files": {}
+        },
+        "id": "introduction",
+        "commit": "58e8cc677a8e2ca5a609cd2ffda8dfa793c6b89b",
+        "title": "Introduction",
+        "page": "<h1>Introduction</h1>\
+",
+        "changes": [
+          {
+            "kind": "added",
+            "path": "include/foo.h"
+          },
+          {
+            "kind": "added",
+            "path": "src/foo.h"
+          }
+        ]
+      }
+    ]
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn build_deltas() {
+    let lines = (1..=20)
+        .map(|n| format!("line {}\n", n))
+        .collect::<String>();
+
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("notes", &lines);
+    project.run("page", &["save", "-m", "\"Page: Introduction\""]);
+    project.run("page", &["new", "Conclusion"]);
+    project.write_file("notes", format!("{}line 21\n", lines));
+    project.run("page", &["save", "-m", "\"Page: Conclusion\""]);
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.contents(".codasai/out/guide.json"),
+        r#"
+{
+  "name": "Simple guide",
+  "vfs": {
+    "files": [
+      {
+        "content": "line 1\
+line 2\
+line 3\
+line 4\
+line 5\
+line 6\
+line 7\
+line 8\
+line 9\
+line 10\
+line 11\
+line 12\
+line 13\
+line 14\
+line 15\
+line 16\
+line 17\
+line 18\
+line 19\
+line 20\
+"
+      },
+      {
+        "delta": {
+          "base": 0,
+          "ops": [
+            {
+              "copy": [
+                0,
+                20
+              ]
+            },
+            {
+              "insert": {
+                "lines": [
+                  "line 21"
+                ]
+              }
+            },
+            {
+              "copy": [
+                20,
+                1
+              ]
+            }
+          ]
+        }
+      }
+    ],
+    "snapshots": [
+      {
+        "root": {
+          "directories": {},
+          "files": {
+            "notes": 0
+          }
+        },
+        "id": "introduction",
+        "commit": "31df16fd5ee93beab463a69128584c477ac3d354",
+        "title": "Introduction",
+        "page": "<h1>Introduction</h1>\
+",
+        "changes": [
+          {
+            "kind": "added",
+            "path": "notes"
+          }
+        ]
+      },
+      {
+        "root": {
+          "directories": {},
+          "files": {
+            "notes": 1
+          }
+        },
+        "id": "conclusion",
+        "commit": "90da58e7fc85bc5c01e7189f1f5efd0b47f121e4",
+        "title": "Conclusion",
+        "page": "<h1>Conclusion</h1>\
+",
+        "changes": [
+          {
+            "kind": "modified",
+            "path": "notes"
+          }
+        ]
+      }
+    ]
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn build_binary_files() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("logo.png", [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a]);
+    project.write_file("data.bin", [0xff, 0x00, 0xfe]);
+    project.run("page", &["save", "-m", "\"Page: Introduction\""]);
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.contents(".codasai/out/guide.json"),
+        r#"
+{
+  "name": "Simple guide",
+  "vfs": {
+    "files": [
+      {
+        "mime": "application/octet-stream",
+        "data": "/wD+"
+      },
+      {
+        "mime": "image/png",
+        "data": "iVBORw0KGgo="
+      }
+    ],
+    "snapshots": [
+      {
+        "root": {
+          "directories": {},
+          "files": {
+            "data.bin": 0,
+            "logo.png": 1
+          }
+        },
+        "id": "introduction",
+        "commit": "583227057edf285a3f89222ff85e9efa8aa06251",
+        "title": "Introduction",
+        "page": "<h1>Introduction</h1>\
+",
+        "changes": [
+          {
+            "kind": "added",
+            "path": "data.bin"
+          },
+          {
+            "kind": "added",
+            "path": "logo.png"
+          }
+        ]
+      }
+    ]
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn build_chapters() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.write_file(
+        ".codasai/guide.toml",
+        r#"title = "Simple guide"
+
+[[chapters]]
+title = "Basics"
+start = "setup"
+
+[[chapters]]
+title = "Advanced"
+start = "_pages/macros.md"
+
+[[chapters]]
+title = "Not written yet"
+start = "async"
+"#,
+    );
+
+    for (n, title) in ["Introduction", "Setup", "Variables", "Macros"]
+        .iter()
+        .enumerate()
+    {
+        project.run("page", &["new", title]);
+        project.write_file("main.rs", format!("fn main() {{ let page = {}; }}\n", n));
+        project.run("page", &["save", "-m", &format!("\"Page: {}\"", title)]);
+    }
+
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Warning: skipping chapter "Not written yet" because its first page "async" is not part of the guide
+
+"#
+    );
+
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+
+    k9::snapshot!(
+        guide["chapters"].to_string(),
+        r#"[{"pages":[0],"title":""},{"pages":[1,2],"title":"Basics"},{"pages":[3],"title":"Advanced"}]"#
+    );
+}
+
+#[test]
+fn build_front_matter() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Toml"]);
+    project.write_file(
+        "_pages/toml.md",
+        r#"+++
+title = "Getting started"
+summary = "Installing the toolchain."
+tags = ["setup"]
+authors = ["Ferris"]
+difficulty = "easy"
++++
+# Toml
+
+Some words.
+"#,
+    );
+    project.run("page", &["save", "-m", "\"Page: Toml\""]);
+
+    project.run("page", &["new", "Yaml"]);
+    project.write_file(
+        "_pages/yaml.md",
+        r#"---
+summary: Writing the first program.
+tags: [basics, hello]
+---
+# Hello world
+
+Some words.
+"#,
+    );
+    project.run("page", &["save", "-m", "\"Page: Yaml\""]);
+
+    project.run("page", &["new", "Long"]);
+    project.write_file(
+        "_pages/long.md",
+        format!("# Long\n\n{}\n", "word ".repeat(450)),
+    );
+    project.run("page", &["save", "-m", "\"Page: Long\""]);
+
+    let output = project.run("build", &[]);
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+    let snapshots = guide["vfs"]["snapshots"].as_array().unwrap();
+
+    k9::snapshot!(
+        snapshots
+            .iter()
+            .map(|snapshot| format!("{} {}", snapshot["title"], snapshot["metadata"]))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        r#"
+"Getting started" {"authors":["Ferris"],"custom":{"difficulty":"easy"},"summary":"Installing the toolchain.","tags":["setup"]}
+"Hello world" {"summary":"Writing the first program.","tags":["basics","hello"]}
+"Long" {"reading_time":2}
+"#
+    );
+}
+
+#[test]
+fn build_page_ids() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Hello World"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.run("page", &["save", "-m", "\"Page: Hello World\""]);
+
+    // Saving the same page file again
+    project.write_file("main.rs", "fn main() { println!(\"Hello\"); }\n");
+    project.run("page", &["save", "-m", "\"Page: Hello World again\""]);
+
+    project.run("page", &["new", "Goodbye"]);
+    project.run("page", &["save", "-m", "\"Page: Goodbye\""]);
+
+    // Pages with the same file name in different directories
+    for directory in ["a", "b"] {
+        let page_path = format!("_pages/{}/intro.md", directory);
+        project.write_file(&page_path, format!("# Intro to {}\n", directory));
+        project.write_file(
+            ".codasai/rev.toml",
+            format!("page_path = \"{}\"\n", page_path),
+        );
+        project.run("page", &["save"]);
+    }
+
+    let output = project.run("build", &[]);
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+
+    k9::snapshot!(
+        guide["vfs"]["snapshots"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|snapshot| snapshot["id"].to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        r#""hello-world" "hello-world-c80975d" "goodbye" "a-intro" "b-intro""#
+    );
+}
+
+#[test]
+fn build_include() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Hello World"]);
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.write_file(
+        "_pages/hello-world.md",
+        r#"# Hello World
+
+```rust
+{{#include main.rs}}
+```
+
+```rust
+{{#include main.rs:2..3}}
+```
+
+Write \{{#include main.rs}} to include a file.
+"#,
+    );
+    project.run("page", &["save"]);
+
+    // The excerpts follow the code of each page
+    project.write_file("main.rs", "fn main() {\n    println!(\"Bye\");\n}\n");
+    project.run("page", &["save", "-m", "Say bye"]);
+
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+    let snapshots = guide["vfs"]["snapshots"].as_array().unwrap();
+
+    k9::snapshot!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot["page"].as_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        r#"
+<h1>Hello World</h1>
+<pre><code>fn main() {
+    println!("Hello");
+}
+</code></pre>
+<pre><code>    println!("Hello");
+</code></pre>
+<p>Write {{#include main.rs}} to include a file.</p>
+
+<h1>Hello World</h1>
+<pre><code>fn main() {
+    println!("Bye");
+}
+</code></pre>
+<pre><code>    println!("Bye");
+</code></pre>
+<p>Write {{#include main.rs}} to include a file.</p>
+
+"#
+    );
+}
+
+#[test]
+fn build_include_missing_lines() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Hello World"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.write_file(
+        "_pages/hello-world.md",
+        "# Hello World\n\n{{#include main.rs:1..5}}\n",
+    );
+    project.run("page", &["save"]);
+
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Error: failed to build page "_pages/hello-world.md"
+
+Caused by:
+    0: failed to expand `{{#include main.rs:1..5}}`
+    1: main.rs has only 1 lines
+
+"#
+    );
+}
+
+#[test]
+fn build_diff() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Hello World"]);
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.write_file(
+        "_pages/hello-world.md",
+        "# Hello World\n\n{{#diff main.rs}}\n",
+    );
+    project.run("page", &["save"]);
+
+    project.run("page", &["new", "Goodbye"]);
+    project.write_file(
+        "main.rs",
+        "fn main() {\n    println!(\"Hello\");\n    println!(\"<Bye>\");\n}\n",
+    );
+    project.write_file(
+        "_pages/goodbye.md",
+        "# Goodbye\n\nSay bye:\n\n{{#diff main.rs}}\n\nDone.\n",
+    );
+    project.run("page", &["save"]);
+
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+    let snapshots = guide["vfs"]["snapshots"].as_array().unwrap();
+
+    k9::snapshot!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot["page"].as_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        r#"
+<h1>Hello World</h1>
+<table class="page-diff">
+<thead><tr><th colspan="3">main.rs</th></tr></thead>
+<tbody>
+<tr class="hunk"><td colspan="3">@@ -0,0 +1,3 @@</td></tr>
+<tr class="added"><td class="line-number"></td><td class="line-number">1</td><td class="code">+fn main() {</td></tr>
+<tr class="added"><td class="line-number"></td><td class="line-number">2</td><td class="code">+    println!("Hello");</td></tr>
+<tr class="added"><td class="line-number"></td><td class="line-number">3</td><td class="code">+}</td></tr>
+</tbody>
+</table>
+
+<h1>Goodbye</h1>
+<p>Say bye:</p>
+<table class="page-diff">
+<thead><tr><th colspan="3">main.rs</th></tr></thead>
+<tbody>
+<tr class="hunk"><td colspan="3">@@ -1,3 +1,4 @@</td></tr>
+<tr class="context"><td class="line-number">1</td><td class="line-number">1</td><td class="code"> fn main() {</td></tr>
+<tr class="context"><td class="line-number">2</td><td class="line-number">2</td><td class="code">     println!("Hello");</td></tr>
+<tr class="added"><td class="line-number"></td><td class="line-number">3</td><td class="code">+    println!("&lt;Bye&gt;");</td></tr>
+<tr class="context"><td class="line-number">3</td><td class="line-number">4</td><td class="code"> }</td></tr>
+</tbody>
+</table>
+<p>Done.</p>
+
+"#
+    );
+}
+
+#[test]
+fn build_changes() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.write_file(
+        "old.rs",
+        "pub fn old() {\n    // A function\n    // to rename\n}\n",
+    );
+    project.write_file("unused.rs", "pub fn unused() {}\n");
+    project.run("page", &["save"]);
+
+    project.run("page", &["new", "Cleanup"]);
+    project.write_file("main.rs", "fn main() { new::new(); }\n");
+    project.remove_file("old.rs");
+    project.write_file(
+        "new.rs",
+        "pub fn new() {\n    // A function\n    // to rename\n}\n",
+    );
+    project.remove_file("unused.rs");
+    project.run("page", &["save"]);
+
+    let output = project.run("build", &[]);
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+    let snapshots = guide["vfs"]["snapshots"].as_array().unwrap();
+
+    k9::snapshot!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot["changes"].to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        r#"
+[{"kind":"added","path":"main.rs"},{"kind":"added","path":"old.rs"},{"kind":"added","path":"unused.rs"}]
+[{"kind":"modified","path":"main.rs"},{"kind":"renamed","old_path":"old.rs","path":"new.rs"},{"kind":"deleted","path":"unused.rs"}]
+"#
+    );
+
+    // The contents of renamed files follow the rename
+    let new_file = guide["vfs"]["snapshots"][1]["root"]["files"]["new.rs"]
+        .as_u64()
+        .unwrap();
+    k9::snapshot!(
+        guide["vfs"]["files"][new_file as usize].to_string(),
+        r#"
+{"content":"pub fn new() {\
+    // A function\
+    // to rename\
+}\
+","highlights":[[[3,"storage"],[1,""],[2,"storage"],[1,""],[3,"entity"]],[[4,""],[13,"comment"]],[[4,""],[12,"comment"]]]}
+"#
+    );
+}
+
+#[test]
+fn build_renames_to_pages() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.write_file("notes.md", "Some notes\nabout the guide\nto keep\n");
+    project.run("page", &["save"]);
+
+    // The notes are moved out of the code
+    project.run("page", &["new", "Archive"]);
+    project.remove_file("notes.md");
+    project.write_file("_pages/notes.md", "Some notes\nabout the guide\nto keep\n");
+    project.run("page", &["save"]);
+
+    // And back in
+    project.run("page", &["new", "Docs"]);
+    project.remove_file("_pages/notes.md");
+    project.write_file("docs/notes.md", "Some notes\nabout the guide\nto keep\n");
+    project.run("page", &["save"]);
+
+    let output = project.run("build", &[]);
+    let guide: serde_json::Value =
+        serde_json::from_str(&output.contents(".codasai/out/guide.json")).unwrap();
+    let snapshots = guide["vfs"]["snapshots"].as_array().unwrap();
+
+    k9::snapshot!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot["changes"].to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        r#"
+[{"kind":"added","path":"main.rs"},{"kind":"added","path":"notes.md"}]
+[{"kind":"deleted","path":"notes.md"}]
+[{"kind":"added","path":"docs/notes.md"}]
+"#
+    );
+    k9::snapshot!(
+        snapshots
+            .iter()
+            .map(|snapshot| snapshot["root"].to_string())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        r#"
+{"directories":{},"files":{"main.rs":0,"notes.md":1}}
+{"directories":{},"files":{"main.rs":0}}
+{"directories":{"docs":{"directories":{},"files":{"notes.md":1}}},"files":{"main.rs":0}}
+"#
+    );
+}
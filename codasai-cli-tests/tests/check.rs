@@ -0,0 +1,117 @@
+use codasai_cli_tests::Project;
+
+fn project_with_references() -> Project {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.write_file(
+        "_pages/introduction.md",
+        "# Introduction
+
+Open <button data-file=\"main.rs\">main.rs</button>, not <button data-file=\"lib.rs\">lib.rs</button>.
+
+<div data-rel=\"main.rs:1..4\">
+
+The whole program.
+
+</div>
+
+<div data-rel=\"main.rs:2..6\">
+
+Past the end.
+
+</div>
+
+<div data-rel=\"main.rs:3..2\">
+
+Empty.
+
+</div>
+
+<div data-rel=\"main.rs\">
+
+No lines.
+
+</div>
+",
+    );
+    project.run("page", &["save"]);
+
+    project
+}
+
+#[test]
+fn check_simple() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.write_file(
+        "_pages/introduction.md",
+        "# Introduction\n\n<div data-rel=\"main.rs:1..2\">\n\nThe program.\n\n</div>\n",
+    );
+    project.run("page", &["save"]);
+    let output = project.run("check", &[]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+All the references of the pages are valid.
+
+"
+    );
+
+    k9::snapshot!(output.stderr(), "");
+}
+
+#[test]
+fn check_broken_references() {
+    let project = project_with_references();
+    let output = project.run("check", &[]);
+
+    k9::snapshot!(output.stdout(), "");
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+error: page 1 (Introduction): data-file="lib.rs": lib.rs does not exist
+error: page 1 (Introduction): data-rel="main.rs:2..6": main.rs has only 3 lines
+error: page 1 (Introduction): data-rel="main.rs:3..2": the line range is empty
+error: page 1 (Introduction): data-rel="main.rs": the line range is missing
+Error: found 4 broken references
+
+"#
+    );
+}
+
+#[test]
+fn check_build() {
+    let project = project_with_references();
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+warning: page 1 (Introduction): data-file="lib.rs": lib.rs does not exist
+warning: page 1 (Introduction): data-rel="main.rs:2..6": main.rs has only 3 lines
+warning: page 1 (Introduction): data-rel="main.rs:3..2": the line range is empty
+warning: page 1 (Introduction): data-rel="main.rs": the line range is missing
+
+"#
+    );
+
+    let output = project.run("build", &["--strict"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+error: page 1 (Introduction): data-file="lib.rs": lib.rs does not exist
+error: page 1 (Introduction): data-rel="main.rs:2..6": main.rs has only 3 lines
+error: page 1 (Introduction): data-rel="main.rs:3..2": the line range is empty
+error: page 1 (Introduction): data-rel="main.rs": the line range is missing
+Error: some pages have broken references, so the guide was not built
+
+"#
+    );
+}
@@ -0,0 +1,61 @@
+use codasai_cli_tests::Project;
+
+#[test]
+fn export_html() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.run("page", &["save", "-m", "\"Page: Introduction\""]);
+    let output = project.run("export", &["--html", "site"]);
+
+    k9::snapshot!(output.stdout(), "");
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.contents("site/guide.js"),
+        r#"
+var CODASAI_GUIDE = "{\\"name\\":\\"Simple guide\\",\\"vfs\\":{\\"files\\":[],\\"snapshots\\":[{\\"root\\":{\\"directories\\":{},\\"files\\":{}},\\"id\\":\\"introduction\\",\\"commit\\":\\"a5b7751a369179285adf5bf93ef7ab4d818a4303\\",\\"title\\":\\"Introduction\\",\\"page\\":\\"<h1>Introduction</h1>\\\
+\\"}]}}";
+
+"#
+    );
+
+    assert!(output.contents("site/index.html").contains("guide.js"));
+}
+
+#[test]
+fn export_single_file() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.run("page", &["save", "-m", "\"Page: Introduction\""]);
+    let output = project.run("export", &["--single-file", "guide.html"]);
+
+    k9::snapshot!(output.stdout(), "");
+
+    k9::snapshot!(output.stderr(), "");
+
+    let html = output.contents("guide.html");
+    assert!(html.contains(
+        r#"const GUIDE = "{\"name\":\"Simple guide\",\"vfs\":{\"files\":[],\"snapshots\":[{\"root\":{\"directories\":{},\"files\":{}},\"id\":\"introduction\",\"commit\":\"a5b7751a369179285adf5bf93ef7ab4d818a4303\",\"title\":\"Introduction\",\"page\":\"<h1>Introduction<\/h1>\\n\"}]}}";"#
+    ));
+    assert!(!html.contains("{{"));
+}
+
+#[test]
+fn export_nothing() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    let output = project.run("export", &[]);
+
+    k9::snapshot!(output.stdout(), "");
+
+    k9::snapshot!(
+        output.stderr(),
+        "
+Error: nothing to export. Pass `--html <dir>` to export the guide as a static site or `--single-file <file>` to export it as a single HTML file
+
+"
+    );
+}
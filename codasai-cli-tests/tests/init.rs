@@ -44,3 +44,33 @@ Error: .codasai directory already exists
 "
     );
 }
+
+#[test]
+fn codasai_cli_init_pages_path() {
+    let project = Project::new();
+    let output = project.run("init", &["Simple Guide", "--pages-path", "docs/steps"]);
+
+    k9::snapshot!(output.stdout(), "");
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.tree(),
+        "
+.codasai/
+    guide.toml
+    rev.toml
+docs/
+    steps/
+"
+    );
+
+    k9::snapshot!(
+        output.contents(".codasai/guide.toml"),
+        r#"
+title = "Simple Guide"
+pages_path = "docs/steps"
+
+"#
+    );
+}
@@ -1,4 +1,4 @@
-use codasai_cli_tests::{Project, WildStr};
+use codasai_cli_tests::Project;
 
 #[test]
 fn page_save_simple() {
@@ -7,13 +7,11 @@ fn page_save_simple() {
     project.run("page", &["new", "Introduction"]);
     let output = project.run("page", &["save", "-m", "Page: Introduction"]);
 
-    assert_eq!(
-        WildStr::from(output.stdout()),
-        "[..] Page: Introduction
- 3 files changed, 3 insertions(+)
- create mode 100644 .codasai/guide.toml
- create mode 100644 .codasai/rev.toml
- create mode 100644 _pages/introduction.md
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved page 1: Introduction
+
 "
     );
 
@@ -31,6 +29,95 @@ _pages/
     );
 }
 
+#[test]
+fn page_save_default_message() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.run("page", &["save"]);
+    project.run("page", &["new", "Setup"]);
+    let output = project.run("page", &["save"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved page 2: Setup
+
+"
+    );
+
+    let output = project.run("page", &["list"]);
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  426dea3  Introduction  _pages/introduction.md  (0 files changed)
+  2  b4b6566  Setup         _pages/setup.md  (0 files changed)
+
+"
+    );
+}
+
+#[test]
+fn page_save_without_changes() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.run("page", &["save"]);
+    let output = project.run("page", &["save"]);
+
+    k9::snapshot!(output.stdout(), "");
+    k9::snapshot!(
+        output.stderr(),
+        "
+Error: there are no changes to save
+
+"
+    );
+}
+
+#[test]
+fn page_save_dates() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+
+    let output = project.run_with_env(
+        "page",
+        &["save"],
+        &[("GIT_AUTHOR_DATE", "the first of January")],
+    );
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Error: failed to parse GIT_AUTHOR_DATE="the first of January"
+
+Caused by:
+    unknown date format. Use a format like `2021-01-01T00:00:00Z`, `Fri, 1 Jan 2021 00:00:00 +0000` or `1609459200 +0000`
+
+"#
+    );
+
+    // The same date as the other tests, so the commit is the same
+    let output = project.run_with_env(
+        "page",
+        &["save"],
+        &[
+            ("GIT_AUTHOR_DATE", "Fri, 1 Jan 2021 00:00:00 +0000"),
+            ("GIT_COMMITTER_DATE", "1609459200 +0000"),
+        ],
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  426dea3  Introduction  _pages/introduction.md  (0 files changed)
+
+"
+    );
+}
+
 #[test]
 fn page_new_simple() {
     let project = Project::new();
@@ -69,3 +156,680 @@ page_path = "_pages/setting-up-the-environment.md"
 "#
     );
 }
+
+#[test]
+fn page_list_simple() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.run("page", &["save", "-m", "Page: Introduction"]);
+
+    project.run("page", &["new", "Hello world"]);
+    project.write_file("src/main.rs", "fn main() {}\n");
+    project.write_file("Cargo.toml", "[package]\n");
+    project.run("page", &["save", "-m", "Page: Hello world"]);
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  a842e7e  Introduction  _pages/introduction.md  (0 files changed)
+  2  a235053  Hello world   _pages/hello-world.md  (2 files changed)
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list", "--json"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        r#"
+[
+  {
+    "index": 1,
+    "title": "Introduction",
+    "page_path": "_pages/introduction.md",
+    "commit": "a842e7ede0d5d91dab6d4fec212ab17e57e78f57",
+    "files_changed": 0
+  },
+  {
+    "index": 2,
+    "title": "Hello world",
+    "page_path": "_pages/hello-world.md",
+    "commit": "a235053d480cc2eed691e8e20b2a7a942df8b1f6",
+    "files_changed": 2
+  }
+]
+
+"#
+    );
+}
+
+#[test]
+fn page_edit_simple() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.run("page", &["save", "-m", "Page: Introduction"]);
+
+    project.run("page", &["new", "Printing"]);
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.run("page", &["save", "-m", "Page: Printing"]);
+
+    let output = project.run("page", &["edit", "introduction"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Editing page 1. Change the page or its code and run `codasai page continue` to save the changes, or `codasai page abort` to discard them.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents("main.rs"),
+        "
+fn main() {}
+
+"
+    );
+
+    project.write_file("_pages/introduction.md", "# Introduction\n\nFixed typo.\n");
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved the changes and replayed the pages after it.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents("main.rs"),
+        r#"
+fn main() {
+    println!("Hello");
+}
+
+"#
+    );
+    k9::snapshot!(
+        output.contents("_pages/introduction.md"),
+        "
+# Introduction
+
+Fixed typo.
+
+"
+    );
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  2846881  Introduction  _pages/introduction.md  (1 file changed)
+  2  bfe6a68  Printing      _pages/printing.md  (1 file changed)
+
+"
+    );
+}
+
+#[test]
+fn page_edit_reused_page_id() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.run("page", &["save", "-m", "Page: Introduction"]);
+
+    // Saving the same page file again
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.run("page", &["save", "-m", "Page: Introduction again"]);
+
+    let output = project.run("page", &["list"]);
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  96e8875  Introduction  _pages/introduction.md  (1 file changed)
+  2  8ce14bc  Introduction  _pages/introduction.md  (1 file changed)
+
+"
+    );
+
+    let output = project.run("page", &["edit", "introduction-8ce14bc"]);
+    k9::snapshot!(
+        output.stdout(),
+        "
+Editing page 2. Change the page or its code and run `codasai page continue` to save the changes, or `codasai page abort` to discard them.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+}
+
+#[test]
+fn page_edit_conflict() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.run("page", &["save", "-m", "Page: Introduction"]);
+
+    project.run("page", &["new", "Printing"]);
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.run("page", &["save", "-m", "Page: Printing"]);
+
+    project.run("page", &["edit", "1"]);
+    project.write_file("main.rs", "fn main() {\n    // TODO\n}\n");
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Error: replaying "Page: Printing" conflicts with the changes in main.rs. Resolve the conflicts and run `codasai page continue`, or run `codasai page abort` to discard the changes.
+
+"#
+    );
+
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Error: "main.rs" still has conflicts. Resolve them and run `codasai page continue` again.
+
+"#
+    );
+
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents("main.rs"),
+        r#"
+fn main() {
+    println!("Hello");
+}
+
+"#
+    );
+}
+
+#[test]
+fn page_edit_abort() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.run("page", &["save", "-m", "Page: Introduction"]);
+
+    project.run("page", &["new", "Printing"]);
+    project.write_file("main.rs", "fn main() {\n    println!(\"Hello\");\n}\n");
+    project.run("page", &["save", "-m", "Page: Printing"]);
+
+    project.run("page", &["edit", "1"]);
+    project.write_file("main.rs", "fn main() {\n    // TODO\n}\n");
+    let output = project.run("page", &["abort"]);
+
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents("main.rs"),
+        r#"
+fn main() {
+    println!("Hello");
+}
+
+"#
+    );
+
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        "
+Error: no page is being edited
+
+"
+    );
+}
+
+/// Saves three pages that add a file each.
+fn three_pages_project() -> Project {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+
+    for title in ["One", "Two", "Three"].iter() {
+        project.run("page", &["new", title]);
+        project.write_file(
+            format!("{}.rs", title.to_lowercase()),
+            format!("// {}\n", title),
+        );
+        project.run("page", &["save", "-m", &format!("Page: {}", title)]);
+    }
+
+    project
+}
+
+#[test]
+fn page_insert_simple() {
+    let project = three_pages_project();
+
+    let output = project.run("page", &["insert", "--after", "1", "One and a half"]);
+
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/one-and-a-half.md"
+
+"#
+    );
+
+    project.write_file("half.rs", "// One and a half\n");
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  7f86353  One             _pages/one.md  (1 file changed)
+  2  e305f54  One and a half  _pages/one-and-a-half.md  (1 file changed)
+  3  ea76b56  Two             _pages/two.md  (1 file changed)
+  4  08b2970  Three           _pages/three.md  (1 file changed)
+
+"
+    );
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/three.md"
+
+"#
+    );
+}
+
+#[test]
+fn page_insert_abort() {
+    let project = three_pages_project();
+
+    project.run("page", &["insert", "--after", "1", "Middle"]);
+    let output = project.run("page", &["abort"]);
+
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.tree(),
+        "
+.codasai/
+    guide.toml
+    rev.toml
+_pages/
+    one.md
+    three.md
+    two.md
+one.rs
+three.rs
+two.rs
+"
+    );
+
+    let output = project.run("page", &["new", "Four"]);
+
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/four.md"
+
+"#
+    );
+}
+
+#[test]
+fn page_insert_existing_page() {
+    let project = three_pages_project();
+
+    let output = project.run("page", &["insert", "--after", "2", "One"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Error: page file "_pages/one.md" already exists
+
+"#
+    );
+    k9::snapshot!(
+        output.contents("_pages/one.md"),
+        "
+# One
+
+"
+    );
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  7f86353  One    _pages/one.md  (1 file changed)
+  2  e37cd7e  Two    _pages/two.md  (1 file changed)
+  3  2652854  Three  _pages/three.md  (1 file changed)
+
+"
+    );
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/three.md"
+
+"#
+    );
+}
+
+#[test]
+fn page_move_simple() {
+    let project = three_pages_project();
+
+    let output = project.run("page", &["move", "three", "--after", "one"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Moved page 3 after page 1.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  7f86353  One    _pages/one.md  (1 file changed)
+  2  b82c415  Three  _pages/three.md  (1 file changed)
+  3  55c146f  Two    _pages/two.md  (1 file changed)
+
+"
+    );
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/two.md"
+
+"#
+    );
+    k9::snapshot!(
+        output.tree(),
+        "
+.codasai/
+    guide.toml
+    rev.toml
+_pages/
+    one.md
+    three.md
+    two.md
+one.rs
+three.rs
+two.rs
+"
+    );
+}
+
+#[test]
+fn page_move_to_front() {
+    let project = three_pages_project();
+
+    let output = project.run("page", &["move", "three", "--before", "1"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Moved page 3 before page 1.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  639c147  Three  _pages/three.md  (1 file changed)
+  2  708b26d  One    _pages/one.md  (1 file changed)
+  3  6f0c61f  Two    _pages/two.md  (1 file changed)
+
+"
+    );
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/two.md"
+
+"#
+    );
+    k9::snapshot!(
+        output.tree(),
+        "
+.codasai/
+    guide.toml
+    rev.toml
+_pages/
+    one.md
+    three.md
+    two.md
+one.rs
+three.rs
+two.rs
+"
+    );
+
+    let output = project.run("page", &["move", "three", "--after", "two"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Moved page 1 after page 3.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  7f86353  One    _pages/one.md  (1 file changed)
+  2  e37cd7e  Two    _pages/two.md  (1 file changed)
+  3  2652854  Three  _pages/three.md  (1 file changed)
+
+"
+    );
+}
+
+#[test]
+fn page_squash_simple() {
+    let project = three_pages_project();
+
+    let output = project.run("page", &["squash", "3"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Squashed page 3 into page 2.
+
+"
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  7f86353  One  _pages/one.md  (1 file changed)
+  2  d6be52e  Two  _pages/two.md  (2 files changed)
+
+"
+    );
+    k9::snapshot!(
+        output.contents("_pages/two.md"),
+        "
+# Two
+
+# Three
+
+"
+    );
+    k9::snapshot!(
+        output.contents(".codasai/rev.toml"),
+        r#"
+page_path = "_pages/two.md"
+
+"#
+    );
+}
+
+#[test]
+fn page_squash_front_matter() {
+    let project = three_pages_project();
+
+    project.run("page", &["edit", "3"]);
+    project.write_file(
+        "_pages/three.md",
+        "+++\ntitle = \"Three\"\ntags = [\"counting\"]\n+++\n# Three\n\nThe last page.\n",
+    );
+    project.run("page", &["continue"]);
+
+    let output = project.run("page", &["squash", "3"]);
+
+    k9::snapshot!(output.stderr(), "");
+    k9::snapshot!(
+        output.contents("_pages/two.md"),
+        "
+# Two
+
+# Three
+
+The last page.
+
+"
+    );
+}
+
+#[test]
+fn page_split_simple() {
+    let project = three_pages_project();
+
+    let output = project.run("page", &["split", "2", "Two and a half"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        r#"
+Splitting page 2. Undo the changes that belong to the new page "Two and a half" and run `codasai page continue`. The new page will get the rest of the changes.
+
+"#
+    );
+    k9::snapshot!(output.stderr(), "");
+
+    // Leave the code of the page to the new page
+    project.remove_file("two.rs");
+    let output = project.run("page", &["continue"]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  7f86353  One             _pages/one.md  (1 file changed)
+  2  8aa9c81  Two             _pages/two.md  (0 files changed)
+  3  b381f72  Two and a half  _pages/two-and-a-half.md  (1 file changed)
+  4  827d94d  Three           _pages/three.md  (1 file changed)
+
+"
+    );
+    k9::snapshot!(
+        output.tree(),
+        "
+.codasai/
+    guide.toml
+    rev.toml
+_pages/
+    one.md
+    three.md
+    two-and-a-half.md
+    two.md
+one.rs
+three.rs
+two.rs
+"
+    );
+}
+
+#[test]
+fn page_custom_pages_path() {
+    let project = Project::new();
+    project.run("init", &["Simple guide", "--pages-path", "docs/steps"]);
+    project.run("page", &["new", "Introduction"]);
+    let output = project.run("page", &["new", "Setup"]);
+
+    k9::snapshot!(
+        output.stderr(),
+        r#"
+Error: There is an unsaved page at "docs/steps/introduction.md". Discard it or save it before retrying.
+
+"#
+    );
+
+    project.run("page", &["save"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.run("page", &["new", "Setup"]);
+    project.run("page", &["save"]);
+    let output = project.run("page", &["list"]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+  1  1e2c5ed  Introduction  docs/steps/introduction.md  (0 files changed)
+  2  0c845c7  Setup         docs/steps/setup.md  (1 file changed)
+
+"
+    );
+
+    let output = project.run("build", &[]);
+
+    k9::snapshot!(output.stderr(), "");
+
+    k9::snapshot!(
+        output.tree(),
+        "
+.codasai/
+    out/
+        guide.json
+    guide.toml
+    rev.toml
+docs/
+    steps/
+        introduction.md
+        setup.md
+main.rs
+"
+    );
+}
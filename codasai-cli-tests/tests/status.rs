@@ -0,0 +1,95 @@
+use codasai_cli_tests::Project;
+
+#[test]
+fn status_without_page() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    let output = project.run("status", &[]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved pages: 0
+No page has been created yet. Run `codasai page new <title>` to start one.
+
+No code changed since the last saved page.
+
+"
+    );
+
+    k9::snapshot!(output.stderr(), "");
+}
+
+#[test]
+fn status_unsaved_page() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    project.write_file("lib.rs", "pub fn lib() {}\n");
+    project.run("page", &["save"]);
+
+    project.run("page", &["new", "Setup"]);
+    project.write_file("main.rs", "fn main() { lib::lib(); }\n");
+    project.remove_file("lib.rs");
+    project.write_file("src/setup.rs", "pub fn setup() {}\n");
+    let output = project.run("status", &[]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved pages: 1
+Current page: Setup (_pages/setup.md)
+The page has not been saved yet.
+
+Code changed since the last saved page:
+    deleted:  lib.rs
+    modified: main.rs
+    added:    src/setup.rs
+
+"
+    );
+
+    k9::snapshot!(output.stderr(), "");
+}
+
+#[test]
+fn status_warnings() {
+    let project = Project::new();
+    project.run("init", &["Simple guide"]);
+    project.run("page", &["new", "Introduction"]);
+    let output = project.run("status", &[]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved pages: 0
+Current page: Introduction (_pages/introduction.md)
+The page has not been saved yet.
+
+No code changed since the last saved page.
+
+warning: the current page does not change any code
+
+"
+    );
+
+    project.run("page", &["save"]);
+    project.write_file("main.rs", "fn main() {}\n");
+    let output = project.run("status", &[]);
+
+    k9::snapshot!(
+        output.stdout(),
+        "
+Saved pages: 1
+Current page: Introduction (_pages/introduction.md)
+The page is saved.
+
+Code changed since the last saved page:
+    added: main.rs
+
+warning: the code changed but the current page is already saved. Run `codasai page new <title>` to explain the changes in a new page.
+
+"
+    );
+}
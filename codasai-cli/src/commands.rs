@@ -1,9 +1,15 @@
 mod build;
+mod check;
+mod export;
 mod init;
 mod page;
 mod serve;
+mod status;
 
 pub use build::build;
+pub use check::check;
+pub use export::export;
 pub use init::init;
 pub use page::page;
 pub use serve::serve;
+pub use status::status;
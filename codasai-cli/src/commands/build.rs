@@ -1,15 +1,40 @@
-use std::fs::{DirBuilder, File, OpenOptions};
-use std::path::Path;
-
-use anyhow::{Context, Result};
-use codasai_types::{Guide, Vfs, VfsPath, VfsSnapshot};
-use git2::{Delta, DiffOptions, Oid, Repository, Tree};
-
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, DirBuilder, File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use codasai_types::{
+    Guide, Vfs, VfsBinary, VfsChange, VfsChangeKind, VfsDirectoryOrFile, VfsPath, VfsSnapshot,
+};
+use git2::{Delta, Diff, DiffFindOptions, DiffOptions, Oid, Repository, Tree};
+use slug::slugify;
+
+use self::chapters::chapters_from_config;
+use self::directives::expand_directives;
+pub(super) use self::directives::FileLines;
+pub(super) use self::front_matter::FrontMatter;
+use self::highlight::Highlighter;
+use super::check::check_guide;
 use crate::config::{GuideConfig, PageConfig};
 use crate::opts::BuildOpts;
 
+mod chapters;
+mod directives;
+mod front_matter;
+mod highlight;
+
 pub fn build(opts: &BuildOpts) -> Result<()> {
-    let guide = guide_from_git(&opts.guide)?;
+    let guide = guide_from_git(&opts.guide, opts.drafts)?;
+
+    let broken_references = check_guide(&guide);
+    let level = if opts.strict { "error" } else { "warning" };
+    for broken_reference in &broken_references {
+        eprintln!("{}: {}", level, broken_reference);
+    }
+    if opts.strict && !broken_references.is_empty() {
+        bail!("some pages have broken references, so the guide was not built");
+    }
+
     write_out_file(&guide, &opts.guide.join(".codasai/out/guide.json"))
 }
 
@@ -42,23 +67,27 @@ fn create_out_file(path: &Path) -> Result<File> {
         .with_context(|| format!("failed to create file {:?}", path))
 }
 
-fn guide_from_git(repo_path: impl AsRef<Path>) -> Result<Guide> {
+/// Builds the guide from the saved pages in the repository at `repo_path`. If
+/// `drafts` is set, the unsaved page and code in the working tree are added as
+/// a draft snapshot at the end.
+pub fn guide_from_git(repo_path: impl AsRef<Path>, drafts: bool) -> Result<Guide> {
     let repo_path = repo_path.as_ref();
 
     let config = GuideConfig::from_file(repo_path.join(".codasai/guide.toml"))?;
 
-    let mut guide = Guide {
-        name: config.title.clone(),
-        vfs: Vfs::new(),
-    };
+    let mut guide = Guide::new(config.title.clone(), Vfs::new());
+    // The path of the page of each snapshot
+    let mut page_paths = Vec::new();
 
     let repo = git2::Repository::open(repo_path)
         .with_context(|| format!("failed to open git repository at {:?}", repo_path))?;
 
-    let mut rev_walker = repo.revwalk()?;
-    rev_walker.set_sorting(git2::Sort::REVERSE)?;
-    rev_walker.push_head().context("you have not saved any page")?;
-    let revs = rev_walker.filter_map(Result::ok).collect::<Vec<_>>();
+    // A draft is enough to build a guide without saved pages
+    let revs = if drafts && repo.is_empty()? {
+        Vec::new()
+    } else {
+        revisions(&repo)?
+    };
 
     // Populate first snapshot with first rev
     if let Some(first_rev) = revs.first() {
@@ -67,8 +96,16 @@ fn guide_from_git(repo_path: impl AsRef<Path>) -> Result<Guide> {
         let rev_config = get_page_config(&repo, *first_rev)?;
         let page = get_page_in_rev(&rev_config, &repo, *first_rev)?;
 
-        let page_html = markdown_to_html(&page);
-        guide.vfs.snapshots.last_mut().unwrap().set_page(page_html);
+        let snapshot = guide.vfs.snapshots.last_mut().unwrap();
+        set_page(snapshot, None, &rev_config.page_path, &page)?;
+        snapshot.set_id(page_id(
+            &config,
+            &repo,
+            Some(*first_rev),
+            &rev_config.page_path,
+        )?);
+        snapshot.set_commit(Some(first_rev.to_string()));
+        page_paths.push(rev_config.page_path);
     }
 
     // Populate snapshots by applying diff between trees
@@ -84,18 +121,60 @@ fn guide_from_git(repo_path: impl AsRef<Path>) -> Result<Guide> {
         let rev_config = get_page_config(&repo, new_rev)?;
         let page = get_page_in_rev(&rev_config, &repo, new_rev)?;
 
-        let page_html = markdown_to_html(&page);
-        new_snapshot.set_page(page_html);
+        set_page(
+            new_snapshot,
+            Some(&new_snapshot_copy),
+            &rev_config.page_path,
+            &page,
+        )?;
+        new_snapshot.set_id(page_id(
+            &config,
+            &repo,
+            Some(new_rev),
+            &rev_config.page_path,
+        )?);
+        new_snapshot.set_commit(Some(new_rev.to_string()));
 
         // If the snapshot didn't change, then don't remove it
         if *new_snapshot == new_snapshot_copy {
             guide.vfs.snapshots.pop();
+        } else {
+            page_paths.push(rev_config.page_path);
+        }
+    }
+
+    if drafts {
+        if let Some(page_path) =
+            add_draft_snapshot(&config, &mut guide.vfs, &repo, revs.last().copied())?
+        {
+            page_paths.push(page_path);
         }
     }
+    ensure!(
+        !guide.vfs.snapshots.is_empty(),
+        "you have not saved any page"
+    );
+
+    guide.chapters = chapters_from_config(&config, &page_paths)?;
+
+    Highlighter::new(&config).highlight_vfs(&mut guide.vfs);
+    store_files_as_deltas(&guide.vfs)?;
 
     Ok(guide)
 }
 
+/// Returns the commits of the guide from the oldest to the newest, which is the
+/// order of its pages.
+pub(super) fn revisions(repo: &Repository) -> Result<Vec<Oid>> {
+    let mut rev_walker = repo.revwalk()?;
+    rev_walker.set_sorting(git2::Sort::REVERSE)?;
+    rev_walker
+        .push_head()
+        .context("you have not saved any page")?;
+
+    Ok(rev_walker.filter_map(Result::ok).collect())
+}
+
 /// Populates a snapshot by replicating the tree from `rev`. This is intended to
 /// be used for the first snapshot only. If used with other snapshots than the
 /// first, the old contents will not be removed.
@@ -105,6 +184,7 @@ fn add_snapshot_from_rev(
     let tree = repo.find_commit(rev)?.tree()?;
 
     let snapshot = vfs.add_snapshot();
+    let mut changes = Vec::new();
     tree.walk(git2::TreeWalkMode::PreOrder, |parent, entry| {
         let file_path = Path::new(parent).join(entry.name().expect("expected a UTF-8 valid name"));
 
@@ -116,12 +196,16 @@ fn add_snapshot_from_rev(
         let object = BlobOrDirectory::from_git_file(repo, &tree, &file_path).unwrap();
 
         match object {
-            BlobOrDirectory::Blob(content) => snapshot.create_file(&file_path_vfs, content),
+            BlobOrDirectory::Blob(content) => {
+                content.create_file(snapshot, &file_path_vfs);
+                changes.push(VfsChange::new(VfsChangeKind::Added, file_path_vfs));
+            },
             BlobOrDirectory::Directory => snapshot.create_directory(&file_path_vfs),
         }
 
         git2::TreeWalkResult::Ok
     })?;
+    snapshot.set_changes(changes);
 
     Ok(())
 }
@@ -137,7 +221,98 @@ fn add_snapshot_from_rev_pair(
     let mut diff_opts = DiffOptions::new();
     diff_opts.minimal(true).patience(true);
 
-    let diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut diff_opts))?;
+    let mut diff =
+        repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut diff_opts))?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    apply_diff(
+        config,
+        snapshot,
+        repo,
+        &diff,
+        Some(&old_tree),
+        &DiffTarget::Tree(&new_tree),
+    )
+}
+
+/// Adds a draft snapshot that matches the working tree, using the page that
+/// `.codasai/rev.toml` points to in the working tree. The snapshot is discarded
+/// if there's nothing unsaved. Returns the path of the page if the snapshot was
+/// added.
+fn add_draft_snapshot(
+    config: &GuideConfig, vfs: &mut Vfs, repo: &Repository, head: Option<Oid>,
+) -> Result<Option<PathBuf>> {
+    let workdir = repo
+        .workdir()
+        .context("bare repositories are not supported")?;
+
+    let rev_toml_path = workdir.join(".codasai/rev.toml");
+    let rev_toml_string = fs::read_to_string(&rev_toml_path)
+        .with_context(|| format!("failed to read {:?}", rev_toml_path))?;
+    let rev_config = PageConfig::from_str(&rev_toml_string)?;
+
+    // There's no page to draft yet
+    if rev_config.page_path.as_os_str().is_empty() {
+        return Ok(None);
+    }
+
+    let page_path = workdir.join(&rev_config.page_path);
+    let page = fs::read_to_string(&page_path)
+        .with_context(|| format!("failed to read page at {:?}", page_path))?;
+
+    let old_tree = head
+        .map(|rev| repo.find_commit(rev).and_then(|commit| commit.tree()))
+        .transpose()?;
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .minimal(true)
+        .patience(true)
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+
+    let mut diff = repo.diff_tree_to_workdir_with_index(old_tree.as_ref(), Some(&mut diff_opts))?;
+    diff.find_similar(Some(
+        DiffFindOptions::new().renames(true).for_untracked(true),
+    ))?;
+
+    let snapshot = vfs.add_snapshot();
+    let snapshot_copy = snapshot.clone();
+
+    apply_diff(
+        config,
+        snapshot,
+        repo,
+        &diff,
+        old_tree.as_ref(),
+        &DiffTarget::Workdir(workdir),
+    )?;
+    set_page(snapshot, Some(&snapshot_copy), &rev_config.page_path, &page)?;
+    snapshot.set_id(page_id(config, repo, None, &rev_config.page_path)?);
+    snapshot.set_commit(None);
+    snapshot.set_draft(true);
+
+    if *snapshot == snapshot_copy {
+        vfs.snapshots.pop();
+        return Ok(None);
+    }
+
+    Ok(Some(rev_config.page_path))
+}
+
+/// Applies the changes in `diff` to `snapshot` and records them as its changes.
+/// The old contents are looked up in `old_tree` and the new contents in
+/// `new_target`.
+fn apply_diff(
+    config: &GuideConfig, snapshot: &mut VfsSnapshot, repo: &Repository, diff: &Diff,
+    old_tree: Option<&Tree>, new_target: &DiffTarget,
+) -> Result<()> {
+    let old_object = |path: &Path| {
+        let old_tree = old_tree.context("expected a tree to diff against")?;
+        BlobOrDirectory::from_git_file(repo, old_tree, path)
+    };
+
+    let mut changes = Vec::new();
 
     for delta in diff.deltas() {
         let old_file_path = delta
@@ -150,40 +325,51 @@ fn add_snapshot_from_rev_pair(
             .path()
             .expect("`DiffFile` does not have a path");
 
-        // Skip files that are part of .codasai/ or _pages/
-        if (old_file_path.starts_with(".codasai/") || old_file_path.starts_with(&config.pages_path))
-            && (new_file_path.starts_with(".codasai/")
-                || new_file_path.starts_with(&config.pages_path))
-        {
-            continue;
-        }
+        // Skip files that are part of .codasai/ or _pages/. A file renamed from
+        // or to them is only added to or deleted from the code.
+        let is_skipped =
+            |path: &Path| path.starts_with(".codasai/") || path.starts_with(&config.pages_path);
+        let status = match (is_skipped(old_file_path), is_skipped(new_file_path)) {
+            (true, true) => continue,
+            (true, false) if delta.status() == Delta::Renamed => Delta::Added,
+            (false, true) if delta.status() == Delta::Renamed => Delta::Deleted,
+            _ => delta.status(),
+        };
 
         let old_file_path_vfs = VfsPath::new(&old_file_path)?;
         let new_file_path_vfs = VfsPath::new(&new_file_path)?;
 
-        match delta.status() {
-            Delta::Added => {
-                let new_object = BlobOrDirectory::from_git_file(repo, &new_tree, new_file_path)?;
+        match status {
+            Delta::Added | Delta::Untracked => {
+                let new_object = new_target.object(repo, new_file_path)?;
                 match new_object {
                     BlobOrDirectory::Blob(content) => {
-                        snapshot.create_file(&new_file_path_vfs, content)
+                        content.create_file(snapshot, &new_file_path_vfs);
+                        changes.push(VfsChange::new(VfsChangeKind::Added, new_file_path_vfs));
                     },
                     BlobOrDirectory::Directory => snapshot.create_directory(&new_file_path_vfs),
                 }
             },
             Delta::Deleted => {
-                let old_object = BlobOrDirectory::from_git_file(repo, &old_tree, old_file_path)?;
-                match old_object {
-                    BlobOrDirectory::Blob(_) => snapshot.delete_file(&old_file_path_vfs),
+                match old_object(old_file_path)? {
+                    BlobOrDirectory::Blob(_) => {
+                        snapshot.delete_file(&old_file_path_vfs);
+                        changes.push(VfsChange::new(VfsChangeKind::Deleted, old_file_path_vfs));
+                    },
                     BlobOrDirectory::Directory => snapshot.delete_directory(&old_file_path_vfs),
                 }
             },
             Delta::Renamed => {
-                let old_object = BlobOrDirectory::from_git_file(repo, &old_tree, old_file_path)?;
-                let new_object = BlobOrDirectory::from_git_file(repo, &new_tree, new_file_path)?;
+                let old_object = old_object(old_file_path)?;
+                let new_object = new_target.object(repo, new_file_path)?;
                 match (old_object, new_object) {
-                    (BlobOrDirectory::Blob(_), BlobOrDirectory::Blob(_)) => {
-                        snapshot.rename_file(&old_file_path_vfs, &new_file_path_vfs)
+                    (BlobOrDirectory::Blob(_), BlobOrDirectory::Blob(new_content)) => {
+                        snapshot.rename_file(&old_file_path_vfs, &new_file_path_vfs);
+                        // The file may have been changed as well as renamed
+                        if !new_content.is_in(snapshot, &new_file_path_vfs) {
+                            new_content.write_file(snapshot, &new_file_path_vfs);
+                        }
+                        changes.push(VfsChange::renamed(old_file_path_vfs, new_file_path_vfs));
                     },
                     (BlobOrDirectory::Directory, BlobOrDirectory::Directory) => {
                         snapshot.rename_directory(&old_file_path_vfs, &new_file_path_vfs)
@@ -192,11 +378,12 @@ fn add_snapshot_from_rev_pair(
                 }
             },
             Delta::Modified => {
-                let old_object = BlobOrDirectory::from_git_file(repo, &old_tree, old_file_path)?;
-                let new_object = BlobOrDirectory::from_git_file(repo, &new_tree, new_file_path)?;
+                let old_object = old_object(old_file_path)?;
+                let new_object = new_target.object(repo, new_file_path)?;
                 match (old_object, new_object) {
                     (BlobOrDirectory::Blob(_), BlobOrDirectory::Blob(new_content)) => {
-                        snapshot.write_file(&new_file_path_vfs, new_content);
+                        new_content.write_file(snapshot, &new_file_path_vfs);
+                        changes.push(VfsChange::new(VfsChangeKind::Modified, new_file_path_vfs));
                     },
                     _ => {
                         unreachable!(
@@ -209,11 +396,71 @@ fn add_snapshot_from_rev_pair(
             _ => {},
         }
     }
+    snapshot.set_changes(changes);
+
+    Ok(())
+}
+
+/// Stores every file as a delta of the version of the same path in the
+/// previous snapshot when the delta is smaller than the whole file.
+fn store_files_as_deltas(vfs: &Vfs) -> Result<()> {
+    // A file is only stored as a delta the first time it appears so its base
+    // always appeared before and can't depend on it
+    let mut visited_files = HashSet::new();
+    let mut previous_files = HashMap::new();
+
+    for snapshot in &vfs.snapshots {
+        let mut current_files = HashMap::new();
+
+        for entry in snapshot.walk() {
+            if !matches!(entry.entry, VfsDirectoryOrFile::File(_)) {
+                continue;
+            }
+
+            let file_index = match snapshot.root.find_file(&entry.path) {
+                Some(file_index) => file_index,
+                None => continue,
+            };
+
+            if visited_files.insert(file_index) {
+                if let Some(&base_index) = previous_files.get(&entry.path) {
+                    store_file_as_delta(vfs, file_index, base_index)?;
+                }
+            }
+
+            current_files.insert(entry.path, file_index);
+        }
+
+        previous_files = current_files;
+    }
+
+    Ok(())
+}
+
+fn store_file_as_delta(vfs: &Vfs, file_index: usize, base_index: usize) -> Result<()> {
+    let (file, delta) = match (
+        vfs.files.file(file_index),
+        vfs.files.delta(file_index, base_index),
+    ) {
+        (Some(file), Some(delta)) => (file, delta),
+        _ => return Ok(()),
+    };
+
+    let file_len = serde_json::to_vec(&file)
+        .context("failed to serialize file")?
+        .len();
+    let delta_len = serde_json::to_vec(&delta)
+        .context("failed to serialize file delta")?
+        .len();
+
+    if delta_len < file_len {
+        vfs.files.set_delta(file_index, delta);
+    }
 
     Ok(())
 }
 
-fn get_page_config(repo: &Repository, rev: Oid) -> Result<PageConfig> {
+pub(super) fn get_page_config(repo: &Repository, rev: Oid) -> Result<PageConfig> {
     let tree = repo.find_commit(rev)?.tree()?;
 
     let rev_entry = tree.get_path(Path::new(".codasai/rev.toml"))?;
@@ -225,7 +472,9 @@ fn get_page_config(repo: &Repository, rev: Oid) -> Result<PageConfig> {
     Ok(rev_config)
 }
 
-fn get_page_in_rev(rev_config: &PageConfig, repo: &Repository, rev: Oid) -> Result<String> {
+pub(super) fn get_page_in_rev(
+    rev_config: &PageConfig, repo: &Repository, rev: Oid,
+) -> Result<String> {
     let tree = repo.find_commit(rev)?.tree()?;
     let page_entry = tree.get_path(&rev_config.page_path)?;
     let page_object = page_entry.to_object(repo)?;
@@ -235,6 +484,109 @@ fn get_page_in_rev(rev_config: &PageConfig, repo: &Repository, rev: Oid) -> Resu
     Ok(page_content)
 }
 
+/// Sets the page of `snapshot`. `previous_snapshot` is the code of the page
+/// before, which is compared with the code of `snapshot` by `{{#diff}}`.
+fn set_page(
+    snapshot: &mut VfsSnapshot, previous_snapshot: Option<&VfsSnapshot>, page_path: &Path,
+    markdown: &str,
+) -> Result<()> {
+    let (mut front_matter, body) = FrontMatter::parse(markdown)
+        .with_context(|| format!("failed to read the front matter of page {:?}", page_path))?;
+
+    let title = front_matter
+        .title
+        .take()
+        .unwrap_or_else(|| page_title(page_path, body));
+
+    let expanded_body = expand_directives(snapshot, previous_snapshot, body)
+        .with_context(|| format!("failed to build page {:?}", page_path))?;
+
+    snapshot.set_title(title);
+    snapshot.set_page(markdown_to_html(&expanded_body));
+    snapshot.set_metadata(front_matter.into_metadata(body));
+
+    Ok(())
+}
+
+/// Returns the title set in the front matter of the page or, if there is none,
+/// its first heading.
+pub(super) fn title_of_page(page_path: &Path, markdown: &str) -> Result<String> {
+    let (front_matter, body) = FrontMatter::parse(markdown)
+        .with_context(|| format!("failed to read the front matter of page {:?}", page_path))?;
+
+    Ok(front_matter
+        .title
+        .unwrap_or_else(|| page_title(page_path, body)))
+}
+
+/// Returns the id of the page of `rev`, or of the draft page if there is no
+/// `rev`. It's the slug of the path of the page file inside `pages_path`
+/// without the extension, e.g. `basics-hello` for `_pages/basics/hello.md`.
+/// A page that reuses the file of an earlier page gets the short id of its
+/// commit as a suffix, so the id doesn't depend on the position of the page.
+pub(super) fn page_id(
+    config: &GuideConfig, repo: &Repository, rev: Option<Oid>, page_path: &Path,
+) -> Result<String> {
+    let relative_path = page_path
+        .strip_prefix(&config.pages_path)
+        .unwrap_or(page_path)
+        .with_extension("");
+    let id = slugify(relative_path.to_string_lossy());
+
+    let parent_tree = match rev {
+        Some(rev) => match repo.find_commit(rev)?.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        },
+        None => repo.head().ok().map(|head| head.peel_to_tree()).transpose()?,
+    };
+    let reused = matches!(parent_tree, Some(tree) if tree.get_path(page_path).is_ok());
+    if !reused {
+        return Ok(id);
+    }
+
+    let suffix = match rev {
+        Some(rev) => repo
+            .find_object(rev, None)?
+            .short_id()?
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        None => "draft".to_string(),
+    };
+
+    Ok(format!("{}-{}", id, suffix))
+}
+
+/// Returns the text of the first heading of the page or the name of the page
+/// file if it has no headings.
+fn page_title(page_path: &Path, markdown: &str) -> String {
+    let mut in_heading = false;
+    let mut title = String::new();
+
+    for event in pulldown_cmark::Parser::new(markdown) {
+        match event {
+            pulldown_cmark::Event::Start(pulldown_cmark::Tag::Heading(_)) => in_heading = true,
+            pulldown_cmark::Event::End(pulldown_cmark::Tag::Heading(_)) => break,
+            pulldown_cmark::Event::Text(text) | pulldown_cmark::Event::Code(text)
+                if in_heading =>
+            {
+                title.push_str(&text)
+            },
+            _ => {},
+        }
+    }
+
+    if title.is_empty() {
+        page_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    } else {
+        title
+    }
+}
+
 fn markdown_to_html(markdown: &str) -> String {
     let mut opts = pulldown_cmark::Options::empty();
     opts.insert(pulldown_cmark::Options::ENABLE_FOOTNOTES);
@@ -249,14 +601,36 @@ fn markdown_to_html(markdown: &str) -> String {
 
     let clean_page_html = ammonia::Builder::new()
         .add_generic_attributes(&["data-rel"])
+        // Diffs of `{{#diff}}`
+        .add_allowed_classes("table", &["page-diff"])
+        .add_allowed_classes("tr", &["hunk", "context", "added", "removed"])
+        .add_allowed_classes("td", &["line-number", "code"])
+        // Buttons that open files in the editor
+        .add_tags(&["button"])
+        .add_tag_attributes("button", &["data-file"])
         .clean(&page_html)
         .to_string();
 
     clean_page_html
 }
 
+/// The side of a diff where the new contents are read from.
+enum DiffTarget<'a> {
+    Tree(&'a Tree<'a>),
+    Workdir(&'a Path),
+}
+
+impl<'a> DiffTarget<'a> {
+    fn object(&self, repo: &Repository, file_path: &Path) -> Result<BlobOrDirectory> {
+        match self {
+            DiffTarget::Tree(tree) => BlobOrDirectory::from_git_file(repo, tree, file_path),
+            DiffTarget::Workdir(workdir) => BlobOrDirectory::from_workdir_file(workdir, file_path),
+        }
+    }
+}
+
 enum BlobOrDirectory {
-    Blob(String),
+    Blob(BlobContent),
     Directory,
 }
 
@@ -268,14 +642,70 @@ impl BlobOrDirectory {
         if is_dir {
             Ok(Self::Directory)
         } else {
-            let object_bytes = object
-                .as_blob()
-                .expect("expected `object` to be a blob")
-                .content();
-            let object_content =
-                String::from_utf8(object_bytes.to_vec()).unwrap_or(String::from("binary data"));
-
-            Ok(Self::Blob(object_content))
+            let blob = object.as_blob().expect("expected `object` to be a blob");
+            let content = if blob.is_binary() {
+                BlobContent::binary(file_path, blob.content().to_vec())
+            } else {
+                BlobContent::from_bytes(file_path, blob.content().to_vec())
+            };
+
+            Ok(Self::Blob(content))
+        }
+    }
+
+    fn from_workdir_file(workdir: &Path, file_path: &Path) -> Result<Self> {
+        let path = workdir.join(file_path);
+
+        if path.is_dir() {
+            Ok(Self::Directory)
+        } else {
+            let bytes = fs::read(&path).with_context(|| format!("failed to read {:?}", path))?;
+
+            Ok(Self::Blob(BlobContent::from_bytes(file_path, bytes)))
+        }
+    }
+}
+
+enum BlobContent {
+    Text(String),
+    Binary(VfsBinary),
+}
+
+impl BlobContent {
+    /// Stores `bytes` as text if they are valid UTF-8 and as a binary file
+    /// otherwise.
+    fn from_bytes(file_path: &Path, bytes: Vec<u8>) -> Self {
+        match String::from_utf8(bytes) {
+            Ok(text) => Self::Text(text),
+            Err(e) => Self::binary(file_path, e.into_bytes()),
+        }
+    }
+
+    /// The MIME type is guessed from the file extension.
+    fn binary(file_path: &Path, bytes: Vec<u8>) -> Self {
+        let mime = mime_guess::from_path(file_path).first_or_octet_stream();
+        Self::Binary(VfsBinary::new(mime.to_string(), bytes))
+    }
+
+    fn create_file(self, snapshot: &mut VfsSnapshot, path: &VfsPath) {
+        match self {
+            Self::Text(content) => snapshot.create_file(path, content),
+            Self::Binary(binary) => snapshot.create_binary_file(path, binary),
+        }
+    }
+
+    /// Returns whether the file at `path` in `snapshot` has this content.
+    fn is_in(&self, snapshot: &VfsSnapshot, path: &VfsPath) -> bool {
+        match self {
+            Self::Text(content) => snapshot.read_file(path).as_ref() == Some(content),
+            Self::Binary(binary) => snapshot.read_binary(path).as_ref() == Some(binary),
+        }
+    }
+
+    fn write_file(self, snapshot: &mut VfsSnapshot, path: &VfsPath) {
+        match self {
+            Self::Text(content) => snapshot.write_file(path, content),
+            Self::Binary(binary) => snapshot.write_binary_file(path, binary),
         }
     }
 }
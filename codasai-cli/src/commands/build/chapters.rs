@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Result};
+use codasai_types::GuideChapter;
+
+use crate::config::{ChapterConfig, GuideConfig};
+
+/// Groups the pages into the chapters of `guide.toml`. `page_paths` are the
+/// paths of the pages of each snapshot.
+///
+/// Chapters that start with a page that is not part of the guide (e.g. a page
+/// that has not been saved yet) are skipped.
+pub fn chapters_from_config(
+    config: &GuideConfig, page_paths: &[PathBuf],
+) -> Result<Vec<GuideChapter>> {
+    let mut starts = Vec::new();
+
+    for chapter in &config.chapters {
+        let start = page_paths
+            .iter()
+            .position(|page_path| is_chapter_start(config, chapter, page_path));
+
+        match start {
+            Some(start) => starts.push((start, chapter)),
+            None => {
+                eprintln!(
+                    "Warning: skipping chapter {:?} because its first page {:?} is not part of \
+                     the guide",
+                    chapter.title, chapter.start
+                );
+            },
+        }
+    }
+
+    ensure!(
+        starts.windows(2).all(|pair| pair[0].0 < pair[1].0),
+        "chapters must be listed in the same order as their pages and must not start with the \
+         same page"
+    );
+
+    let mut chapters = Vec::new();
+
+    if let Some(&(first_start, _)) = starts.first() {
+        if first_start > 0 {
+            chapters.push(GuideChapter {
+                title: String::new(),
+                pages: (0..first_start).collect(),
+            });
+        }
+    }
+
+    for (i, &(start, chapter)) in starts.iter().enumerate() {
+        let end = starts
+            .get(i + 1)
+            .map(|&(next_start, _)| next_start)
+            .unwrap_or_else(|| page_paths.len());
+
+        chapters.push(GuideChapter {
+            title: chapter.title.clone(),
+            pages: (start..end).collect(),
+        });
+    }
+
+    Ok(chapters)
+}
+
+fn is_chapter_start(config: &GuideConfig, chapter: &ChapterConfig, page_path: &Path) -> bool {
+    if page_path == Path::new(&chapter.start) {
+        return true;
+    }
+
+    match page_path.strip_prefix(&config.pages_path) {
+        Ok(page_name) => page_name.with_extension("") == Path::new(&chapter.start),
+        Err(_) => false,
+    }
+}
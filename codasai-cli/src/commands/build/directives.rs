@@ -0,0 +1,217 @@
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use codasai_types::{VfsPath, VfsSnapshot};
+use git2::{DiffOptions, Patch};
+use pulldown_cmark::escape::escape_html;
+
+/// Replaces the `{{#name args}}` directives in `markdown` with what they
+/// produce from the code of `snapshot`:
+///
+/// - `{{#include $path}}` and `{{#include $path:$line_start..$line_end}}` are
+///   replaced with the contents of the file or of the lines, without the last
+///   line break. Like in `data-rel`, the lines start at 1 and the end is not
+///   included.
+/// - `{{#diff $path}}` is replaced with a table of the changes to the file
+///   since `previous_snapshot`.
+///
+/// A directive is written as is if it starts with `\{{#`.
+pub fn expand_directives(
+    snapshot: &VfsSnapshot, previous_snapshot: Option<&VfsSnapshot>, markdown: &str,
+) -> Result<String> {
+    let mut expanded = String::with_capacity(markdown.len());
+    let mut rest = markdown;
+
+    while let Some(start) = rest.find("{{#") {
+        if rest[..start].ends_with('\\') {
+            expanded.push_str(&rest[..start - 1]);
+            expanded.push_str("{{#");
+            rest = &rest[start + 3..];
+            continue;
+        }
+
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .ok_or_else(|| anyhow!("directive `{}` is not closed", first_line(&rest[start..])))?;
+
+        let directive = &rest[start + 3..end];
+        let (name, args) = directive
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((directive.trim(), ""));
+
+        let output = match name {
+            "include" => include(snapshot, args.trim()),
+            "diff" => diff(snapshot, previous_snapshot, args.trim()),
+            _ => Err(anyhow!("unknown directive")),
+        }
+        .with_context(|| format!("failed to expand `{{{{#{}}}}}`", directive.trim()))?;
+
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&output);
+        rest = &rest[end + 2..];
+    }
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
+fn include(snapshot: &VfsSnapshot, args: &str) -> Result<String> {
+    let file_lines = FileLines::parse(args)?;
+    let content = read_file(snapshot, file_lines.path)?;
+
+    file_lines.select(&content)
+}
+
+/// Renders the unified diff of the file at `path` between `previous_snapshot`
+/// and `snapshot` as a table with the old and new line numbers.
+fn diff(
+    snapshot: &VfsSnapshot, previous_snapshot: Option<&VfsSnapshot>, path: &str,
+) -> Result<String> {
+    let vfs_path = VfsPath::new(path)?;
+    let old_content = match previous_snapshot {
+        Some(previous_snapshot) => read_text_file(previous_snapshot, &vfs_path)?,
+        None => None,
+    };
+    let new_content = read_text_file(snapshot, &vfs_path)?;
+    if old_content.is_none() && new_content.is_none() {
+        bail!("{} does not exist", path);
+    }
+
+    let old_content = old_content.unwrap_or_default();
+    let new_content = new_content.unwrap_or_default();
+    let patch = Patch::from_buffers(
+        old_content.as_bytes(),
+        Some(Path::new(path)),
+        new_content.as_bytes(),
+        Some(Path::new(path)),
+        Some(DiffOptions::new().patience(true)),
+    )?;
+    if patch.num_hunks() == 0 {
+        bail!("{} did not change in this page", path);
+    }
+
+    let mut html = String::from("<table class=\"page-diff\">\n");
+    html.push_str("<thead><tr><th colspan=\"3\">");
+    escape_html(&mut html, path)?;
+    html.push_str("</th></tr></thead>\n<tbody>\n");
+
+    for hunk_index in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_index)?;
+        html.push_str("<tr class=\"hunk\"><td colspan=\"3\">");
+        escape_html(&mut html, String::from_utf8_lossy(hunk.header()).trim_end())?;
+        html.push_str("</td></tr>\n");
+
+        for line_index in 0..line_count {
+            let line = patch.line_in_hunk(hunk_index, line_index)?;
+            let class = match line.origin() {
+                ' ' => "context",
+                '+' => "added",
+                '-' => "removed",
+                // Markers of a missing line break at the end of the file
+                _ => continue,
+            };
+            let line_number =
+                |number: Option<u32>| number.map(|n| n.to_string()).unwrap_or_default();
+
+            html.push_str(&format!(
+                "<tr class=\"{}\"><td class=\"line-number\">{}</td><td class=\"line-number\">{}</td><td class=\"code\">{}",
+                class,
+                line_number(line.old_lineno()),
+                line_number(line.new_lineno()),
+                line.origin(),
+            ));
+            escape_html(
+                &mut html,
+                String::from_utf8_lossy(line.content()).trim_end_matches(&['\r', '\n'][..]),
+            )?;
+            html.push_str("</td></tr>\n");
+        }
+    }
+    html.push_str("</tbody>\n</table>");
+
+    Ok(html)
+}
+
+/// Reads a text file of the snapshot.
+fn read_file(snapshot: &VfsSnapshot, path: &str) -> Result<String> {
+    read_text_file(snapshot, &VfsPath::new(path)?)?
+        .ok_or_else(|| anyhow!("{} does not exist", path))
+}
+
+/// Reads a text file of the snapshot if it exists.
+fn read_text_file(snapshot: &VfsSnapshot, path: &VfsPath) -> Result<Option<String>> {
+    match snapshot.read_file(path) {
+        Some(content) => Ok(Some(content)),
+        None if snapshot.read_binary(path).is_some() => bail!("{} is a binary file", path),
+        None => Ok(None),
+    }
+}
+
+/// A file or some of its lines, written `$path` or
+/// `$path:$line_start..$line_end` in `{{#include}}` and `data-rel`. The lines
+/// start at 1 and the end is not included.
+pub struct FileLines<'a> {
+    pub path: &'a str,
+    pub lines: Option<Range<usize>>,
+}
+
+impl<'a> FileLines<'a> {
+    /// Parses `reference`. It only has lines if what follows its last `:`
+    /// contains `..`, so paths can contain `:`.
+    pub fn parse(reference: &'a str) -> Result<Self> {
+        let (path, lines) = match reference.rsplit_once(':') {
+            Some((path, lines)) if lines.contains("..") => (path, lines),
+            _ => {
+                return Ok(Self {
+                    path: reference,
+                    lines: None,
+                })
+            },
+        };
+
+        let (start, end) = lines
+            .split_once("..")
+            .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+            .ok_or_else(|| anyhow!("{:?} is not a line range like `1..4`", lines))?;
+
+        if start == 0 {
+            bail!("the lines start at 1");
+        }
+        if end <= start {
+            bail!("the line range is empty");
+        }
+
+        Ok(Self {
+            path,
+            lines: Some(start..end),
+        })
+    }
+
+    /// Returns the lines of `content`, the content of the file, without the
+    /// last line break. It fails if the file doesn't have all the lines.
+    pub fn select(&self, content: &str) -> Result<String> {
+        let lines = match &self.lines {
+            Some(lines) => lines,
+            None => return Ok(content.strip_suffix('\n').unwrap_or(content).to_string()),
+        };
+
+        let line_count = content.lines().count();
+        if lines.end - 1 > line_count {
+            bail!("{} has only {} lines", self.path, line_count);
+        }
+
+        Ok(content
+            .lines()
+            .skip(lines.start - 1)
+            .take(lines.end - lines.start)
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use codasai_types::PageMetadata;
+use serde::Deserialize;
+
+/// Average number of words read per minute, used to estimate the reading time.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// The front matter of a page. It's written at the start of the page either as
+/// TOML between `+++` lines or as YAML between `---` lines.
+#[derive(Debug, Default, Deserialize)]
+pub struct FrontMatter {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    #[serde(flatten)]
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+impl FrontMatter {
+    /// Splits `markdown` into its front matter and the rest of the page. Pages
+    /// without front matter get an empty one.
+    pub fn parse(markdown: &str) -> Result<(Self, &str)> {
+        if let Some((front_matter, body)) = split_delimited(markdown, "+++") {
+            let front_matter =
+                toml::from_str(front_matter).context("failed to parse TOML front matter")?;
+            Ok((front_matter, body))
+        } else if let Some((front_matter, body)) = split_delimited(markdown, "---") {
+            // An empty YAML document is `null`
+            let front_matter = if front_matter.trim().is_empty() {
+                Self::default()
+            } else {
+                serde_yaml::from_str(front_matter).context("failed to parse YAML front matter")?
+            };
+            Ok((front_matter, body))
+        } else {
+            Ok((Self::default(), markdown))
+        }
+    }
+
+    /// Returns the metadata of a page with this front matter and `body`.
+    pub fn into_metadata(self, body: &str) -> PageMetadata {
+        let words = body.split_whitespace().count();
+        let minutes = (words + WORDS_PER_MINUTE / 2) / WORDS_PER_MINUTE;
+
+        PageMetadata {
+            summary: self.summary,
+            reading_time: (minutes > 0).then_some(minutes as u32),
+            tags: self.tags,
+            authors: self.authors,
+            custom: self.custom,
+        }
+    }
+}
+
+/// Splits `text` into the lines between the first line and the next line that
+/// are equal to `delimiter` and everything after them.
+fn split_delimited<'a>(text: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let mut lines = text.split_inclusive('\n');
+
+    let first_line = lines.next()?;
+    if first_line.trim_end() != delimiter {
+        return None;
+    }
+
+    let start = first_line.len();
+    let mut end = start;
+    for line in lines {
+        if line.trim_end() == delimiter {
+            return Some((&text[start..end], &text[end + line.len()..]));
+        }
+
+        end += line.len();
+    }
+
+    None
+}
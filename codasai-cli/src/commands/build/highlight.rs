@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap};
+
+use codasai_types::{Vfs, VfsDirectoryOrFile, VfsFile, VfsPath, VfsToken};
+use syntect::parsing::{ParseState, ScopeStack, ScopeStackOp, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use crate::config::GuideConfig;
+
+/// Scopes that are given a class. Any other scope is considered plain text.
+const HIGHLIGHTED_SCOPES: &[&str] = &[
+    "comment", "constant", "entity", "invalid", "keyword", "markup", "storage", "string",
+    "support", "variable",
+];
+
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+    overrides: BTreeMap<String, String>,
+}
+
+impl Highlighter {
+    pub fn new(config: &GuideConfig) -> Self {
+        let highlighter = Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            overrides: config.syntaxes.clone(),
+        };
+
+        for (pattern, syntax) in &highlighter.overrides {
+            if highlighter.syntax_by_name(syntax).is_none() {
+                eprintln!(
+                    "warning: unknown syntax {:?} for {:?} in guide.toml, so its syntax is \
+                     detected instead",
+                    syntax, pattern
+                );
+            }
+        }
+
+        highlighter
+    }
+
+    /// Highlights every file of every snapshot in `vfs`. Files whose syntax
+    /// can't be detected are left as plain text.
+    ///
+    /// Files with the same content share their highlighting, so a file that is
+    /// highlighted with another syntax than a file with the same content (e.g.
+    /// `a.h` and `a.cpp`) is stored again.
+    pub fn highlight_vfs(&self, vfs: &mut Vfs) {
+        // The syntax each file was highlighted with
+        let mut file_syntaxes: HashMap<usize, Option<String>> = HashMap::new();
+        // The copies of files highlighted with another syntax
+        let mut copies: HashMap<(usize, Option<String>), usize> = HashMap::new();
+
+        for snapshot in &mut vfs.snapshots {
+            let file_paths = snapshot
+                .walk()
+                .filter(|entry| matches!(entry.entry, VfsDirectoryOrFile::File(_)))
+                .map(|entry| entry.path)
+                .collect::<Vec<_>>();
+
+            for path in file_paths {
+                let file_index = match snapshot.root.find_file(&path) {
+                    Some(file_index) => file_index,
+                    None => continue,
+                };
+
+                // Binary files are not highlighted
+                let content = match vfs.files.read_file(file_index) {
+                    Some(content) => content,
+                    None => continue,
+                };
+                let syntax = self.find_syntax(&path, &content);
+                let syntax_name = syntax.map(|syntax| syntax.name.clone());
+
+                match file_syntaxes.get(&file_index) {
+                    Some(file_syntax) if *file_syntax == syntax_name => continue,
+                    Some(_) => {
+                        let key = (file_index, syntax_name);
+                        let copy_index = match copies.get(&key) {
+                            Some(&copy_index) => copy_index,
+                            None => {
+                                let highlights = syntax
+                                    .map(|syntax| self.highlight(syntax, &content))
+                                    .unwrap_or_default();
+                                let copy_index = vfs.files.add_distinct_file(VfsFile::Full {
+                                    content,
+                                    highlights,
+                                });
+                                copies.insert(key, copy_index);
+                                copy_index
+                            },
+                        };
+                        snapshot.root.create_file(&path, copy_index);
+                    },
+                    None => {
+                        if let Some(syntax) = syntax {
+                            vfs.files
+                                .set_highlights(file_index, self.highlight(syntax, &content));
+                        }
+                        file_syntaxes.insert(file_index, syntax_name);
+                    },
+                }
+            }
+        }
+    }
+
+    /// Finds the syntax of a file. The syntaxes set in `guide.toml` have
+    /// priority over the file extension and the first line of the file (e.g.
+    /// a shebang). An override for the path of the file has priority over one
+    /// for its name, which has priority over the `*` patterns, the longest
+    /// first.
+    fn find_syntax(&self, path: &VfsPath, content: &str) -> Option<&SyntaxReference> {
+        let file_name = path.file_name();
+        let overridden_syntax = self
+            .overrides
+            .get(path.as_str())
+            .or_else(|| self.overrides.get(file_name))
+            .or_else(|| {
+                self.overrides
+                    .iter()
+                    .filter(|(pattern, _)| {
+                        matches!(pattern.strip_prefix('*'), Some(suffix) if file_name.ends_with(suffix))
+                    })
+                    .max_by_key(|(pattern, _)| pattern.len())
+                    .map(|(_, syntax)| syntax)
+            });
+
+        if let Some(syntax) = overridden_syntax.and_then(|syntax| self.syntax_by_name(syntax)) {
+            return Some(syntax);
+        }
+
+        let extension = file_name.rsplit('.').next().unwrap_or(file_name);
+        self.syntaxes
+            .find_syntax_by_extension(file_name)
+            .or_else(|| self.syntaxes.find_syntax_by_extension(extension))
+            .or_else(|| {
+                let first_line = content.lines().next()?;
+                self.syntaxes.find_syntax_by_first_line(first_line)
+            })
+            .filter(|syntax| syntax.name != "Plain Text")
+    }
+
+    /// Finds a syntax by its name (e.g. `Rust`) or by one of its extensions
+    /// (e.g. `rs`).
+    fn syntax_by_name(&self, name: &str) -> Option<&SyntaxReference> {
+        self.syntaxes
+            .find_syntax_by_name(name)
+            .or_else(|| self.syntaxes.find_syntax_by_token(name))
+    }
+
+    fn highlight(&self, syntax: &SyntaxReference, content: &str) -> Vec<Vec<VfsToken>> {
+        let mut parse_state = ParseState::new(syntax);
+        let mut scope_stack = ScopeStack::new();
+        let mut highlights = Vec::new();
+
+        for line in LinesWithEndings::from(content) {
+            let line_len = line.trim_end_matches('\n').len();
+            let ops = parse_state.parse_line(line, &self.syntaxes);
+
+            let mut tokens: Vec<VfsToken> = Vec::new();
+            let mut start = 0;
+
+            for (end, op) in ops.into_iter().chain(Some((line_len, ScopeStackOp::Noop))) {
+                let end = end.min(line_len);
+                if end > start {
+                    let class = class_of(&scope_stack);
+
+                    match tokens.last_mut() {
+                        Some(last_token) if last_token.class == class => {
+                            last_token.len += end - start
+                        },
+                        _ => tokens.push(VfsToken::new(end - start, class)),
+                    }
+
+                    start = end;
+                }
+
+                scope_stack.apply(&op);
+            }
+
+            // Trailing plain text is implied
+            if matches!(tokens.last(), Some(token) if token.class.is_empty()) {
+                tokens.pop();
+            }
+
+            highlights.push(tokens);
+        }
+
+        while matches!(highlights.last(), Some(tokens) if tokens.is_empty()) {
+            highlights.pop();
+        }
+
+        highlights
+    }
+}
+
+/// Returns the class of the innermost scope that is highlighted.
+fn class_of(scope_stack: &ScopeStack) -> String {
+    scope_stack
+        .as_slice()
+        .iter()
+        .rev()
+        .find_map(|scope| {
+            let scope = scope.build_string();
+            let kind = scope.split('.').next().unwrap_or_default();
+
+            HIGHLIGHTED_SCOPES
+                .contains(&kind)
+                .then(|| kind.to_string())
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use anyhow::{bail, Result};
+use codasai_types::{Guide, VfsPath, VfsSnapshot};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+};
+
+use super::build::{guide_from_git, FileLines};
+use crate::opts::CheckOpts;
+
+/// A `data-file` or `data-rel` attribute of a page that doesn't point to a file
+/// or to lines of the code of the page.
+#[derive(Debug)]
+pub(super) struct BrokenReference {
+    /// The number of the page, starting at 1.
+    pub page: usize,
+    pub title: String,
+    /// The attribute as written in the page (e.g. `data-rel="main.rs:1..3"`).
+    pub reference: String,
+    pub reason: String,
+}
+
+impl fmt::Display for BrokenReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "page {} ({}): {}: {}",
+            self.page, self.title, self.reference, self.reason
+        )
+    }
+}
+
+pub fn check(opts: &CheckOpts) -> Result<()> {
+    let guide = guide_from_git(&opts.guide, opts.drafts)?;
+    let broken_references = check_guide(&guide);
+
+    if broken_references.is_empty() {
+        println!("All the references of the pages are valid.");
+        return Ok(());
+    }
+
+    for broken_reference in &broken_references {
+        eprintln!("error: {}", broken_reference);
+    }
+    bail!(
+        "found {} broken reference{}",
+        broken_references.len(),
+        if broken_references.len() == 1 {
+            ""
+        } else {
+            "s"
+        }
+    );
+}
+
+/// Checks that the files and lines referenced by each page exist in the code of
+/// that page.
+pub(super) fn check_guide(guide: &Guide) -> Vec<BrokenReference> {
+    let mut broken_references = Vec::new();
+
+    for (index, snapshot) in guide.vfs.snapshots.iter().enumerate() {
+        for (name, value) in page_references(&snapshot.page) {
+            let reason = match name.as_str() {
+                "data-file" => check_file(snapshot, &value).err(),
+                _ => check_lines(snapshot, &value).err(),
+            };
+
+            if let Some(reason) = reason {
+                broken_references.push(BrokenReference {
+                    page: index + 1,
+                    title: snapshot.title.clone(),
+                    reference: format!("{}={:?}", name, value),
+                    reason,
+                });
+            }
+        }
+    }
+
+    broken_references
+}
+
+/// Checks `$path` and returns it.
+fn check_file(snapshot: &VfsSnapshot, path: &str) -> Result<VfsPath, String> {
+    let path = VfsPath::new(path).map_err(|_| "the path is not valid".to_string())?;
+
+    if snapshot.read_file(&path).is_none() && snapshot.read_binary(&path).is_none() {
+        return Err(format!("{} does not exist", path));
+    }
+
+    Ok(path)
+}
+
+/// Checks `$path:$line_start..$line_end`. The lines start at 1 and the end is
+/// not included.
+fn check_lines(snapshot: &VfsSnapshot, rel: &str) -> Result<(), String> {
+    let file_lines = FileLines::parse(rel).map_err(|error| error.to_string())?;
+    if file_lines.lines.is_none() {
+        return Err("the line range is missing".to_string());
+    }
+    let path = check_file(snapshot, file_lines.path)?;
+
+    let content = snapshot
+        .read_file(&path)
+        .ok_or_else(|| format!("{} is a binary file", path))?;
+    file_lines
+        .select(&content)
+        .map_err(|error| error.to_string())?;
+
+    Ok(())
+}
+
+/// Returns the name and value of the `data-file` and `data-rel` attributes in
+/// `html`.
+fn page_references(html: &str) -> Vec<(String, String)> {
+    let mut queue = BufferQueue::new();
+    queue.push_back(StrTendril::from(html));
+
+    let mut tokenizer = Tokenizer::new(ReferenceSink::default(), TokenizerOpts::default());
+    let _ = tokenizer.feed(&mut queue);
+    tokenizer.end();
+
+    tokenizer.sink.references
+}
+
+#[derive(Default)]
+struct ReferenceSink {
+    references: Vec<(String, String)>,
+}
+
+impl TokenSink for ReferenceSink {
+    type Handle = ();
+
+    fn process_token(&mut self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        if let Token::TagToken(tag) = token {
+            if tag.kind == TagKind::StartTag {
+                for attr in tag.attrs {
+                    let name = &*attr.name.local;
+                    if name == "data-file" || name == "data-rel" {
+                        self.references
+                            .push((name.to_string(), attr.value.to_string()));
+                    }
+                }
+            }
+        }
+
+        TokenSinkResult::Continue
+    }
+}
@@ -0,0 +1,130 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, ensure, Context, Result};
+use codasai_server::EmbeddedFile;
+
+use super::build::guide_from_git;
+use crate::opts::ExportOpts;
+
+pub fn export(opts: &ExportOpts) -> Result<()> {
+    ensure!(
+        opts.html.is_some() || opts.single_file.is_some(),
+        "nothing to export. Pass `--html <dir>` to export the guide as a static site or \
+         `--single-file <file>` to export it as a single HTML file"
+    );
+
+    let guide = guide_from_git(&opts.guide, false)?;
+    let guide_json = serde_json::to_string(&guide).context("failed to serialize guide")?;
+
+    if let Some(ref html_dir) = opts.html {
+        export_html_dir(&guide_json, html_dir)?;
+    }
+
+    if let Some(ref html_file) = opts.single_file {
+        export_single_file(&guide_json, html_file)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a static site with the web app and the guide to `dir`. All the URLs
+/// are relative so it can be served from any path by any static file server.
+fn export_html_dir(guide_json: &str, dir: &Path) -> Result<()> {
+    let public_dir = dir.join("public");
+    fs::create_dir_all(&public_dir)
+        .with_context(|| format!("failed to create directory {:?}", public_dir))?;
+
+    for EmbeddedFile(path, contents) in codasai_server::public_files() {
+        // The pages are generated below
+        if path.extension() == Some(OsStr::new("html")) {
+            continue;
+        }
+
+        write_file(&public_dir.join(path), contents)?;
+    }
+
+    write_file(
+        &dir.join("index.html"),
+        codasai_server::export_html().as_bytes(),
+    )?;
+
+    let guide_js = format!(
+        "var CODASAI_GUIDE = {};\n",
+        serde_json::to_string(guide_json)?
+    );
+    write_file(&dir.join("guide.js"), guide_js.as_bytes())?;
+
+    Ok(())
+}
+
+/// Writes an HTML file with the web app and the guide inlined to `path`. It
+/// doesn't depend on any other file so it can be opened from `file://`.
+fn export_single_file(guide_json: &str, path: &Path) -> Result<()> {
+    let wasm_module = public_file("codasai_web_bg.wasm")?;
+    let wasm_glue = String::from_utf8(public_file("codasai_web.js")?.to_vec())
+        .context("the web app glue code is not valid UTF-8")?;
+
+    let html = fill_template(codasai_server::single_file_html(), |placeholder| {
+        let value = match placeholder {
+            "markdown_css" => String::from_utf8_lossy(public_file("markdown.css")?).into_owned(),
+            "style_css" => String::from_utf8_lossy(public_file("style.css")?).into_owned(),
+            "guide" => js_string(guide_json)?,
+            "wasm_glue" => js_string(&wasm_glue)?,
+            "wasm_module" => js_string(&base64::encode(wasm_module))?,
+            _ => {
+                bail!(
+                    "unknown placeholder `{}` in single file template",
+                    placeholder
+                )
+            },
+        };
+
+        Ok(value)
+    })?;
+
+    write_file(path, html.as_bytes())
+}
+
+fn public_file(path: &str) -> Result<&'static [u8]> {
+    codasai_server::public_file(path)
+        .map(|EmbeddedFile(_, contents)| contents)
+        .with_context(|| format!("the web app is missing the file {:?}", path))
+}
+
+/// Encodes `s` as a JavaScript string literal that can be embedded in a
+/// `<script>` tag.
+fn js_string(s: &str) -> Result<String> {
+    Ok(serde_json::to_string(s)?.replace("</", "<\\/"))
+}
+
+/// Replaces every `{{ placeholder }}` in `template` with the value returned
+/// by `value`.
+fn fill_template(template: &str, mut value: impl FnMut(&str) -> Result<String>) -> Result<String> {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .context("unclosed placeholder in template")?;
+
+        filled.push_str(&rest[..start]);
+        filled.push_str(&value(rest[start + 2..end].trim())?);
+        rest = &rest[end + 2..];
+    }
+    filled.push_str(rest);
+
+    Ok(filled)
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {:?}", parent))?;
+    }
+
+    fs::write(path, contents).with_context(|| format!("failed to write to file {:?}", path))
+}
@@ -5,6 +5,7 @@ use std::path::Path;
 use anyhow::{ensure, Context, Result};
 use indoc::writedoc;
 
+use crate::config::default_pages_path;
 use crate::opts::InitOpts;
 
 pub fn init(opts: &InitOpts) -> Result<()> {
@@ -13,18 +14,27 @@ pub fn init(opts: &InitOpts) -> Result<()> {
         ".codasai directory already exists"
     );
 
+    if let Some(pages_path) = &opts.pages_path {
+        ensure!(
+            pages_path.is_relative() && !pages_path.starts_with(".codasai"),
+            "the pages path must be relative to the guide and outside of .codasai"
+        );
+    }
+
     let title = &opts.title;
 
     if !dir_exists(".git")? {
         init_git_repo()?;
     }
-    create_dotcodasai_dir(&title)?;
-    create_pages_dir()?;
+    create_dotcodasai_dir(&title, opts.pages_path.as_deref())?;
+
+    let pages_path = opts.pages_path.clone().unwrap_or_else(default_pages_path);
+    create_pages_dir(&pages_path)?;
 
     Ok(())
 }
 
-fn create_dotcodasai_dir(title: &str) -> Result<()> {
+fn create_dotcodasai_dir(title: &str, pages_path: Option<&Path>) -> Result<()> {
     fs::create_dir("./.codasai")?;
 
     // .codasai/guide.toml
@@ -41,6 +51,11 @@ fn create_dotcodasai_dir(title: &str) -> Result<()> {
         title
     )?;
 
+    if let Some(pages_path) = pages_path {
+        let pages_path = toml::Value::String(pages_path.to_string_lossy().into_owned());
+        writeln!(guide_toml, "pages_path = {}", pages_path)?;
+    }
+
     // .codasai/rev.toml
     let mut guide_toml = fs::OpenOptions::new()
         .create(true)
@@ -54,9 +69,8 @@ fn create_dotcodasai_dir(title: &str) -> Result<()> {
     Ok(())
 }
 
-fn create_pages_dir() -> Result<()> {
-    let pages_path = Path::new("./_pages");
-    fs::create_dir(pages_path)
+fn create_pages_dir(pages_path: &Path) -> Result<()> {
+    fs::create_dir_all(pages_path)
         .with_context(|| format!("failed to create directory at {:?}", pages_path))?;
 
     Ok(())
@@ -1,47 +1,169 @@
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use anyhow::{bail, Context, Result};
-use git2::Repository;
+use anyhow::{bail, ensure, Context, Result};
+use git2::{Index, IndexAddOption, Oid, Repository};
 use indoc::writedoc;
+use serde::Serialize;
 use slug::slugify;
 use toml_edit;
 
-use crate::config::PageConfig;
-use crate::opts::{PageNewOpts, PageOpts, PageSaveOpts, PageSubcmd};
+pub(super) use self::rewrite::is_editing;
+use self::rewrite::{abort_editing, continue_editing, edit, insert, move_page, split, squash};
+use super::build::{get_page_config, get_page_in_rev, page_id, revisions, title_of_page};
+use crate::config::{GuideConfig, PageConfig};
+use crate::opts::{PageListOpts, PageNewOpts, PageOpts, PageSaveOpts, PageSubcmd};
 use crate::util::{self, path};
 
+mod rewrite;
+
 pub fn page(opts: &PageOpts) -> Result<()> {
     match opts.subcmd {
         PageSubcmd::New(ref opts) => new(opts),
         PageSubcmd::Save(ref opts) => save(opts),
+        PageSubcmd::List(ref opts) => list(opts),
+        PageSubcmd::Edit(ref opts) => edit(opts),
+        PageSubcmd::Insert(ref opts) => insert(opts),
+        PageSubcmd::Move(ref opts) => move_page(opts),
+        PageSubcmd::Squash(ref opts) => squash(opts),
+        PageSubcmd::Split(ref opts) => split(opts),
+        PageSubcmd::Continue => continue_editing(),
+        PageSubcmd::Abort => abort_editing(),
     }
 }
 
+/// A saved page as shown by `page list`.
+#[derive(Debug, Clone, Serialize)]
+struct PageEntry {
+    /// The position of the page in the guide, starting at 1.
+    index: usize,
+    title: String,
+    page_path: PathBuf,
+    commit: String,
+    /// The number of files of the code changed by the page.
+    files_changed: usize,
+}
+
+pub fn list(opts: &PageListOpts) -> Result<()> {
+    let repo = open_repository()?;
+    let config = GuideConfig::load()?;
+
+    let mut entries = Vec::new();
+    let mut old_rev = None;
+
+    for rev in revisions(&repo)? {
+        let rev_config = get_page_config(&repo, rev)?;
+        let page = get_page_in_rev(&rev_config, &repo, rev)?;
+
+        entries.push(PageEntry {
+            index: entries.len() + 1,
+            title: title_of_page(&rev_config.page_path, &page)?,
+            files_changed: count_changed_files(&config, &repo, old_rev, rev)?,
+            page_path: rev_config.page_path,
+            commit: rev.to_string(),
+        });
+
+        old_rev = Some(rev);
+    }
+
+    if opts.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    let title_width = entries
+        .iter()
+        .map(|entry| entry.title.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    for entry in &entries {
+        let short_commit = repo
+            .find_object(Oid::from_str(&entry.commit)?, None)?
+            .short_id()?;
+
+        println!(
+            "{:>3}  {}  {:<title_width$}  {}  ({} {} changed)",
+            entry.index,
+            short_commit.as_str().unwrap_or_default(),
+            entry.title,
+            entry.page_path.display(),
+            entry.files_changed,
+            if entry.files_changed == 1 { "file" } else { "files" },
+            title_width = title_width,
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the number of files that changed between `old_rev` and `new_rev`,
+/// not counting the pages and the guide configuration.
+fn count_changed_files(
+    config: &GuideConfig, repo: &Repository, old_rev: Option<Oid>, new_rev: Oid,
+) -> Result<usize> {
+    let old_tree = old_rev
+        .map(|rev| repo.find_commit(rev).and_then(|commit| commit.tree()))
+        .transpose()?;
+    let new_tree = repo.find_commit(new_rev)?.tree()?;
+
+    let diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), None)?;
+    let is_code_file = |file_path: Option<&Path>| {
+        matches!(file_path, Some(file_path)
+            if !file_path.starts_with(".codasai/") && !file_path.starts_with(&config.pages_path))
+    };
+
+    Ok(diff
+        .deltas()
+        .filter(|delta| {
+            is_code_file(delta.old_file().path()) || is_code_file(delta.new_file().path())
+        })
+        .count())
+}
+
 pub fn new(opts: &PageNewOpts) -> Result<()> {
     let repo_path = PathBuf::from(".").canonicalize()?;
 
     let repo = Repository::open(".")
         .with_context(|| format!("failed to open repository at {:?}", repo_path))?;
 
-    if let Some(unsaved_page) = find_unsaved_page(&repo)? {
+    let config = GuideConfig::load()?;
+    if let Some(unsaved_page) = find_unsaved_page(&config, &repo)? {
         bail!(
             "There is an unsaved page at {:?}. Discard it or save it before retrying.",
             unsaved_page
         );
     }
 
-    let title = &opts.title;
+    create_page(&opts.title)?;
 
-    let new_page_path = Path::new("_pages/").join(format!("{}.md", slugify(&title)));
+    Ok(())
+}
 
-    let mut new_page = fs::OpenOptions::new()
-        .create(true)
+/// Creates the file of a new page in the `pages_path` of the guide and makes it
+/// the current page, and returns its path. It fails if the file of the page
+/// already exists.
+fn create_page(title: &str) -> Result<PathBuf> {
+    let config = GuideConfig::load()?;
+    fs::create_dir_all(&config.pages_path)
+        .with_context(|| format!("failed to create directory at {:?}", config.pages_path))?;
+    let new_page_path = new_page_path(&config, title);
+
+    let mut new_page = match fs::OpenOptions::new()
         .write(true)
+        .create_new(true)
         .open(&new_page_path)
-        .with_context(|| format!("failed to create page at {:?}", new_page_path))?;
+    {
+        Ok(new_page) => new_page,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            bail!("page file {:?} already exists", new_page_path)
+        },
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to create page at {:?}", new_page_path))
+        },
+    };
 
     writedoc!(
         new_page,
@@ -57,6 +179,24 @@ pub fn new(opts: &PageNewOpts) -> Result<()> {
     rev_toml["page_path"] = toml_edit::value(new_page_path.to_string_lossy().into_owned());
     fs::write(&rev_toml_path, rev_toml.to_string().as_bytes())?;
 
+    Ok(new_page_path)
+}
+
+/// Returns the path of the file of a new page with the given title.
+fn new_page_path(config: &GuideConfig, title: &str) -> PathBuf {
+    config.pages_path.join(format!("{}.md", slugify(title)))
+}
+
+/// Fails if the file of a new page with the given title already exists, before
+/// anything is changed.
+fn ensure_new_page(title: &str) -> Result<()> {
+    let page_path = new_page_path(&GuideConfig::load()?, title);
+    ensure!(
+        !page_path.exists(),
+        "page file {:?} already exists",
+        page_path
+    );
+
     Ok(())
 }
 
@@ -76,21 +216,153 @@ pub fn save(opts: &PageSaveOpts) -> Result<()> {
         bail!("page `{}` does not exist", rev_toml.page_path.display());
     }
 
-    Command::new("git").args(&["add", "-A"]).output()?;
+    let repo = open_repository()?;
+    if is_editing(&repo)? {
+        bail!("a page is being edited. Run `codasai page continue` to save it instead.");
+    }
+
+    let tree = repo.find_tree(stage_all(&repo)?.write_tree()?)?;
+    let parent = match repo.head() {
+        Ok(head) => Some(head.peel_to_commit()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e).context("failed to find the last saved page"),
+    };
+
+    let unchanged = match &parent {
+        Some(parent) => parent.tree_id() == tree.id(),
+        None => tree.is_empty(),
+    };
+    if unchanged {
+        bail!("there are no changes to save");
+    }
 
-    let mut commit_command = Command::new("git");
-    commit_command.arg("commit");
+    let page = fs::read_to_string(&rev_toml.page_path)
+        .with_context(|| format!("failed to read page at {:?}", rev_toml.page_path))?;
+    let title = title_of_page(&rev_toml.page_path, &page)?;
+    let message = opts.message.clone().unwrap_or_else(|| title.clone());
 
-    if let Some(ref message) = opts.message {
-        commit_command.args(&["-m", message]);
+    run_hook(&repo, "pre-commit", &[])?;
+    let message = run_commit_msg_hook(&repo, message)?;
+    // Clean up the message like `git commit` does
+    let message = git2::message_prettify(message, None)?;
+    if message.is_empty() {
+        bail!("the commit message of the page is empty");
     }
 
-    commit_command.spawn()?.wait_with_output()?;
+    let (author, committer) = util::git::signatures(&repo)?;
+    let parents = parent.iter().collect::<Vec<_>>();
+    repo.commit(Some("HEAD"), &author, &committer, &message, &tree, &parents)
+        .context("failed to save the page")?;
+
+    let page_number = revisions(&repo)?.len();
+    println!("Saved page {}: {}", page_number, title);
+
+    Ok(())
+}
+
+/// Runs the git hook `name` if the repository has it.
+fn run_hook(repo: &Repository, name: &str, args: &[&Path]) -> Result<()> {
+    let hooks_path = match repo.config()?.get_path("core.hooksPath") {
+        Ok(hooks_path) => hooks_path,
+        Err(_) => repo.path().join("hooks"),
+    };
+    let hook_path = hooks_path.join(name);
+    if !hook_path.is_file() {
+        return Ok(());
+    }
+
+    let workdir = repo
+        .workdir()
+        .context("bare repositories are not supported")?;
+    let status = Command::new(&hook_path)
+        .args(args)
+        .current_dir(workdir)
+        .status()
+        .with_context(|| format!("failed to run the {} hook at {:?}", name, hook_path))?;
+
+    if !status.success() {
+        bail!("the {} hook failed, so the page was not saved", name);
+    }
 
     Ok(())
 }
 
-fn find_unsaved_page(repo: &Repository) -> Result<Option<PathBuf>> {
+/// Runs the `commit-msg` hook, which can change the message, and returns the
+/// final message.
+fn run_commit_msg_hook(repo: &Repository, message: String) -> Result<String> {
+    let message_path = repo.path().join("COMMIT_EDITMSG");
+    fs::write(&message_path, &message)
+        .with_context(|| format!("failed to write to file {:?}", message_path))?;
+
+    run_hook(repo, "commit-msg", &[&message_path])?;
+
+    fs::read_to_string(&message_path).with_context(|| format!("failed to read {:?}", message_path))
+}
+
+pub(super) fn open_repository() -> Result<Repository> {
+    let repo_path = PathBuf::from(".").canonicalize()?;
+
+    Repository::open(".").with_context(|| format!("failed to open repository at {:?}", repo_path))
+}
+
+/// Returns the position in `revs` of the page given by its number, as shown by
+/// `page list`, or by its id, as shown in the URLs of the guide.
+fn find_page(repo: &Repository, revs: &[Oid], page: &str) -> Result<usize> {
+    if let Ok(number) = page.parse::<usize>() {
+        ensure!(
+            (1..=revs.len()).contains(&number),
+            "there is no page {}, the guide has {} pages",
+            number,
+            revs.len()
+        );
+
+        return Ok(number - 1);
+    }
+
+    let config = GuideConfig::load()?;
+    for (index, rev) in revs.iter().enumerate() {
+        let rev_config = get_page_config(repo, *rev)?;
+
+        if page_id(&config, repo, Some(*rev), &rev_config.page_path)? == page {
+            return Ok(index);
+        }
+    }
+
+    bail!("there is no page with id {:?}", page)
+}
+
+/// Stages the changes to all the files that are not ignored, including the
+/// deleted ones. The output of `codasai build` is never staged.
+fn stage_all(repo: &Repository) -> Result<Index> {
+    let mut skip_build_output = |path: &Path, _: &[u8]| -> i32 {
+        if is_build_output(path) {
+            1
+        } else {
+            0
+        }
+    };
+
+    let mut index = repo.index()?;
+    index
+        .add_all(
+            ["*"].iter(),
+            IndexAddOption::DEFAULT,
+            Some(&mut skip_build_output),
+        )
+        .context("failed to stage the changes")?;
+    index
+        .update_all(["*"].iter(), None)
+        .context("failed to stage the changes")?;
+    index.write()?;
+
+    Ok(index)
+}
+
+fn is_build_output(path: &Path) -> bool {
+    path.starts_with(".codasai/out/")
+}
+
+fn find_unsaved_page(config: &GuideConfig, repo: &Repository) -> Result<Option<PathBuf>> {
     let statuses = repo
         .statuses(None)
         .context("failed to get status of repository")?;
@@ -98,7 +370,7 @@ fn find_unsaved_page(repo: &Repository) -> Result<Option<PathBuf>> {
     for status in statuses.iter() {
         let path = status.path().context("unexpected non utf-8 file path")?;
         let path = PathBuf::from(path);
-        if path.starts_with("_pages/") {
+        if path.starts_with(&config.pages_path) {
             return Ok(Some(path));
         }
     }
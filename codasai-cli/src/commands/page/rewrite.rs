@@ -0,0 +1,592 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context, Result};
+use git2::build::CheckoutBuilder;
+use git2::{Commit, FileMode, Oid, Repository, ResetType, StatusOptions, Tree};
+use serde::{Deserialize, Serialize};
+
+use super::{create_page, ensure_new_page, find_page, is_build_output, open_repository, stage_all};
+use crate::commands::build::{get_page_config, revisions, FrontMatter};
+use crate::opts::{PageEditOpts, PageInsertOpts, PageMoveOpts, PageSplitOpts, PageSquashOpts};
+use crate::util::git::signatures;
+
+const REV_TOML_PATH: &str = ".codasai/rev.toml";
+const GUIDE_TOML_PATH: &str = ".codasai/guide.toml";
+
+/// A rewrite of the guide history that is in progress: the pages that have to
+/// be replayed on top of HEAD. It's stored in the git directory so it can't be
+/// saved with a page by mistake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EditState {
+    /// The branch that is updated once all the pages are replayed.
+    branch: String,
+    /// The commit the branch pointed to before editing, restored by `page
+    /// abort`.
+    original_head: String,
+    /// The page whose replay stopped because of a conflict.
+    current: Option<String>,
+    /// The pages that have not been replayed yet, in order.
+    remaining: Vec<String>,
+    /// The commit the pages are replayed on when the first page changes. It
+    /// only holds the guide config and is not kept in the history.
+    #[serde(default)]
+    placeholder: Option<String>,
+    pending: PendingChanges,
+}
+
+/// What `page continue` does with the changes in the working tree before
+/// replaying the remaining pages, unless a replay stopped because of a
+/// conflict.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum PendingChanges {
+    /// Amends the checked out page.
+    Amend,
+    /// Saves them as a new page. `page_path` is the file of the page, which
+    /// `page abort` removes.
+    Insert { message: String, page_path: PathBuf },
+    /// Saves them as the first part of the page `commit` and the rest of its
+    /// changes as a new page.
+    Split {
+        commit: String,
+        title: String,
+        message: String,
+    },
+}
+
+impl EditState {
+    /// Checks that the guide can be rewritten and returns the state that puts
+    /// everything back as it is now.
+    fn start(repo: &Repository) -> Result<Self> {
+        if EditState::load(repo)?.is_some() {
+            bail!(
+                "a page is already being edited. Run `codasai page continue` or `codasai page \
+                 abort` first."
+            );
+        }
+        ensure_clean(repo)?;
+
+        let head = repo.head().context("you have not saved any page")?;
+        if !head.is_branch() {
+            bail!("HEAD must point to a branch to edit a page");
+        }
+        let branch = head
+            .name()
+            .context("unexpected non utf-8 branch name")?
+            .to_string();
+
+        Ok(Self {
+            branch,
+            original_head: head.peel_to_commit()?.id().to_string(),
+            current: None,
+            remaining: Vec::new(),
+            placeholder: None,
+            pending: PendingChanges::Amend,
+        })
+    }
+
+    fn path(repo: &Repository) -> PathBuf {
+        repo.path().join("codasai-edit.toml")
+    }
+
+    fn load(repo: &Repository) -> Result<Option<Self>> {
+        let path = Self::path(repo);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let state_string =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {:?}", path))?;
+        let state = toml::from_str(&state_string)
+            .with_context(|| format!("failed to process {:?}", path))?;
+
+        Ok(Some(state))
+    }
+
+    fn save(&self, repo: &Repository) -> Result<()> {
+        let path = Self::path(repo);
+        fs::write(&path, toml::to_string(self)?)
+            .with_context(|| format!("failed to write to file {:?}", path))
+    }
+
+    fn remove(repo: &Repository) -> Result<()> {
+        let path = Self::path(repo);
+        fs::remove_file(&path).with_context(|| format!("failed to remove file {:?}", path))
+    }
+}
+
+/// Returns whether a page is being edited, inserted, split, etc.
+pub fn is_editing(repo: &Repository) -> Result<bool> {
+    Ok(EditState::load(repo)?.is_some())
+}
+
+pub fn edit(opts: &PageEditOpts) -> Result<()> {
+    let repo = open_repository()?;
+    let mut state = EditState::start(&repo)?;
+
+    let revs = revisions(&repo)?;
+    let page_index = find_page(&repo, &revs, &opts.page)?;
+
+    checkout(&repo, revs[page_index])?;
+    state.remaining = to_strings(&revs[page_index + 1..]);
+    state.save(&repo)?;
+
+    println!(
+        "Editing page {}. Change the page or its code and run `codasai page continue` to save \
+         the changes, or `codasai page abort` to discard them.",
+        page_index + 1
+    );
+
+    Ok(())
+}
+
+pub fn insert(opts: &PageInsertOpts) -> Result<()> {
+    let repo = open_repository()?;
+    let mut state = EditState::start(&repo)?;
+
+    let revs = revisions(&repo)?;
+    let page_index = find_page(&repo, &revs, &opts.after)?;
+    ensure_new_page(&opts.title)?;
+
+    checkout(&repo, revs[page_index])?;
+    let page_path = create_page(&opts.title)?;
+    state.remaining = to_strings(&revs[page_index + 1..]);
+    state.pending = PendingChanges::Insert {
+        message: opts.message.clone().unwrap_or_else(|| opts.title.clone()),
+        page_path,
+    };
+    state.save(&repo)?;
+
+    println!(
+        "Inserted page {:?} after page {}. Write the page and its code and run `codasai page \
+         continue` to save it, or `codasai page abort` to discard it.",
+        opts.title,
+        page_index + 1
+    );
+
+    Ok(())
+}
+
+pub fn move_page(opts: &PageMoveOpts) -> Result<()> {
+    let repo = open_repository()?;
+    let mut state = EditState::start(&repo)?;
+
+    let revs = revisions(&repo)?;
+    let page_index = find_page(&repo, &revs, &opts.page)?;
+    let (position, target) = match (&opts.after, &opts.before) {
+        (Some(after), _) => ("after", after),
+        (None, Some(before)) => ("before", before),
+        (None, None) => bail!("either --after or --before is required"),
+    };
+    let target_index = find_page(&repo, &revs, target)?;
+    ensure!(
+        page_index != target_index,
+        "a page can't be moved {} itself",
+        position
+    );
+
+    let mut new_revs = revs.clone();
+    let page_rev = new_revs.remove(page_index);
+    // Removing the page shifts the pages after it
+    let target_new_index = if target_index > page_index {
+        target_index - 1
+    } else {
+        target_index
+    };
+    let new_index = if opts.after.is_some() {
+        target_new_index + 1
+    } else {
+        target_new_index
+    };
+    new_revs.insert(new_index, page_rev);
+
+    if new_revs == revs {
+        println!(
+            "Page {} is already {} page {}.",
+            page_index + 1,
+            position,
+            target_index + 1
+        );
+        return Ok(());
+    }
+
+    // The pages before the first moved one stay as they are
+    let first_changed = page_index.min(new_index);
+    if first_changed == 0 {
+        let placeholder = placeholder_commit(&repo, revs[0])?;
+        checkout(&repo, placeholder)?;
+        state.placeholder = Some(placeholder.to_string());
+    } else {
+        checkout(&repo, new_revs[first_changed - 1])?;
+    }
+    state.remaining = to_strings(&new_revs[first_changed..]);
+    state.save(&repo)?;
+
+    replay(&repo, state)?;
+    println!(
+        "Moved page {} {} page {}.",
+        page_index + 1,
+        position,
+        target_index + 1
+    );
+
+    Ok(())
+}
+
+pub fn squash(opts: &PageSquashOpts) -> Result<()> {
+    let repo = open_repository()?;
+    let mut state = EditState::start(&repo)?;
+    let workdir = repo
+        .workdir()
+        .context("bare repositories are not supported")?;
+
+    let revs = revisions(&repo)?;
+    let page_index = find_page(&repo, &revs, &opts.page)?;
+    ensure!(
+        page_index != 0,
+        "the first page can't be squashed because there is no page before it"
+    );
+
+    let previous_commit = repo.find_commit(revs[page_index - 1])?;
+    let previous_page_path = get_page_config(&repo, previous_commit.id())?.page_path;
+    let page_path = get_page_config(&repo, revs[page_index])?.page_path;
+
+    // Keep the code of the page and its text at the end of the previous page
+    checkout(&repo, revs[page_index])?;
+    if page_path != previous_page_path {
+        let page = read_file(&workdir.join(&page_path))?;
+        // The page before keeps its own front matter
+        let (_, body) = FrontMatter::parse(&page)
+            .with_context(|| format!("failed to read the front matter of page {:?}", page_path))?;
+        let mut previous_page = read_file(&workdir.join(&previous_page_path))?;
+        previous_page.push('\n');
+        previous_page.push_str(body.trim_start_matches('\n'));
+
+        write_file(&workdir.join(&previous_page_path), &previous_page)?;
+        fs::remove_file(workdir.join(&page_path))
+            .with_context(|| format!("failed to remove page at {:?}", page_path))?;
+    }
+    write_rev_toml(&repo, &previous_commit)?;
+
+    let tree = repo.find_tree(stage_all(&repo)?.write_tree()?)?;
+    let parents = previous_commit.parents().collect::<Vec<_>>();
+    let squashed_commit = repo.commit(
+        None,
+        &previous_commit.author(),
+        &previous_commit.committer(),
+        previous_commit
+            .message()
+            .context("unexpected non utf-8 commit message")?,
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )?;
+    repo.set_head_detached(squashed_commit)?;
+
+    state.remaining = to_strings(&revs[page_index + 1..]);
+    state.save(&repo)?;
+
+    replay(&repo, state)?;
+    println!("Squashed page {} into page {}.", page_index + 1, page_index);
+
+    Ok(())
+}
+
+pub fn split(opts: &PageSplitOpts) -> Result<()> {
+    let repo = open_repository()?;
+    let mut state = EditState::start(&repo)?;
+
+    let revs = revisions(&repo)?;
+    let page_index = find_page(&repo, &revs, &opts.page)?;
+    ensure!(page_index != 0, "the first page can't be split");
+    ensure_new_page(&opts.title)?;
+
+    // Leave the changes of the page in the working tree
+    checkout(&repo, revs[page_index])?;
+    let previous_commit = repo.find_commit(revs[page_index - 1])?;
+    repo.reset(previous_commit.as_object(), ResetType::Mixed, None)?;
+
+    state.remaining = to_strings(&revs[page_index + 1..]);
+    state.pending = PendingChanges::Split {
+        commit: revs[page_index].to_string(),
+        title: opts.title.clone(),
+        message: opts.message.clone().unwrap_or_else(|| opts.title.clone()),
+    };
+    state.save(&repo)?;
+
+    println!(
+        "Splitting page {}. Undo the changes that belong to the new page {:?} and run `codasai \
+         page continue`. The new page will get the rest of the changes.",
+        page_index + 1,
+        opts.title
+    );
+
+    Ok(())
+}
+
+pub fn continue_editing() -> Result<()> {
+    let repo = open_repository()?;
+    let mut state = EditState::load(&repo)?.context("no page is being edited")?;
+
+    ensure_conflicts_resolved(&repo)?;
+    let tree = repo.find_tree(stage_all(&repo)?.write_tree()?)?;
+    let head_commit = repo.head()?.peel_to_commit()?;
+
+    if let Some(current) = state.current.take() {
+        let commit = repo.find_commit(Oid::from_str(&current)?)?;
+        replay_commit(&repo, &state, &commit, &tree)?;
+    } else {
+        match &state.pending {
+            PendingChanges::Amend => {
+                if tree.id() != head_commit.tree_id() {
+                    head_commit.amend(Some("HEAD"), None, None, None, None, Some(&tree))?;
+                }
+            },
+            PendingChanges::Insert { message, .. } => commit_new_page(&repo, message, &tree)?,
+            PendingChanges::Split {
+                commit,
+                title,
+                message,
+            } => {
+                let commit = repo.find_commit(Oid::from_str(commit)?)?;
+                replay_commit(&repo, &state, &commit, &tree)?;
+
+                repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().force()))
+                    .context("failed to check out the rest of the page")?;
+                create_page(title)?;
+
+                let tree = repo.find_tree(stage_all(&repo)?.write_tree()?)?;
+                commit_new_page(&repo, message, &tree)?;
+            },
+        }
+    }
+    state.pending = PendingChanges::Amend;
+
+    replay(&repo, state)?;
+    println!("Saved the changes and replayed the pages after it.");
+
+    Ok(())
+}
+
+pub fn abort_editing() -> Result<()> {
+    let repo = open_repository()?;
+    let state = EditState::load(&repo)?.context("no page is being edited")?;
+
+    repo.cleanup_state()?;
+    let original_head = repo.find_commit(Oid::from_str(&state.original_head)?)?;
+    repo.reset(original_head.as_object(), ResetType::Hard, None)
+        .context("failed to restore the guide")?;
+    repo.set_head(&state.branch)?;
+
+    // The file of an inserted page is not tracked yet, so the reset keeps it
+    if let PendingChanges::Insert { page_path, .. } = &state.pending {
+        let workdir = repo
+            .workdir()
+            .context("bare repositories are not supported")?;
+        let page_path = workdir.join(page_path);
+        if page_path.exists() {
+            fs::remove_file(&page_path)
+                .with_context(|| format!("failed to remove file {:?}", page_path))?;
+        }
+    }
+    EditState::remove(&repo)?;
+
+    println!("Discarded the changes to the page.");
+
+    Ok(())
+}
+
+/// Replays the remaining pages on top of HEAD and points the branch to the
+/// result. If a page conflicts, it stops and saves the state so `page
+/// continue` can pick it up once the conflicts are resolved.
+fn replay(repo: &Repository, mut state: EditState) -> Result<()> {
+    while !state.remaining.is_empty() {
+        let commit = repo.find_commit(Oid::from_str(&state.remaining.remove(0))?)?;
+        repo.cherrypick(&commit, None)?;
+        write_rev_toml(repo, &commit)?;
+
+        let mut index = repo.index()?;
+        index.add_path(Path::new(REV_TOML_PATH))?;
+        index.write()?;
+
+        if index.has_conflicts() {
+            let conflicts = conflicting_paths(repo)?;
+            state.current = Some(commit.id().to_string());
+            state.save(repo)?;
+
+            bail!(
+                "replaying {:?} conflicts with the changes in {}. Resolve the conflicts and run \
+                 `codasai page continue`, or run `codasai page abort` to discard the changes.",
+                commit.summary().unwrap_or_default(),
+                conflicts.join(", ")
+            );
+        }
+
+        let tree = repo.find_tree(index.write_tree()?)?;
+        replay_commit(repo, &state, &commit, &tree)?;
+    }
+
+    let new_head = repo.head()?.peel_to_commit()?.id();
+    repo.reference(&state.branch, new_head, true, "codasai: edit page")?;
+    repo.set_head(&state.branch)?;
+    EditState::remove(repo)
+}
+
+/// Commits `tree` on top of HEAD with the message and signatures of `commit`.
+/// On top of the placeholder, it's committed as the first page instead.
+fn replay_commit(repo: &Repository, state: &EditState, commit: &Commit, tree: &Tree) -> Result<()> {
+    let parent = repo.head()?.peel_to_commit()?;
+    let is_placeholder = state.placeholder.as_deref() == Some(&parent.id().to_string());
+    let parents = if is_placeholder {
+        vec![]
+    } else {
+        vec![&parent]
+    };
+    let message = commit
+        .message()
+        .context("unexpected non utf-8 commit message")?;
+
+    let new_commit = repo.commit(
+        None,
+        &commit.author(),
+        &commit.committer(),
+        message,
+        tree,
+        &parents,
+    )?;
+    repo.set_head_detached(new_commit)?;
+    repo.cleanup_state()?;
+
+    Ok(())
+}
+
+fn commit_new_page(repo: &Repository, message: &str, tree: &Tree) -> Result<()> {
+    let parent = repo.head()?.peel_to_commit()?;
+    let (author, committer) = signatures(repo)?;
+
+    repo.commit(Some("HEAD"), &author, &committer, message, tree, &[&parent])?;
+
+    Ok(())
+}
+
+/// Commits the guide config of the page of `first_rev` alone, for the pages
+/// to be replayed on when the first page changes.
+fn placeholder_commit(repo: &Repository, first_rev: Oid) -> Result<Oid> {
+    let first_commit = repo.find_commit(first_rev)?;
+    let guide_toml = first_commit
+        .tree()?
+        .get_path(Path::new(GUIDE_TOML_PATH))
+        .context("the first page has no guide config")?;
+
+    let mut dotcodasai = repo.treebuilder(None)?;
+    dotcodasai.insert("guide.toml", guide_toml.id(), guide_toml.filemode())?;
+    let mut root = repo.treebuilder(None)?;
+    root.insert(".codasai", dotcodasai.write()?, i32::from(FileMode::Tree))?;
+    let tree = repo.find_tree(root.write()?)?;
+
+    let placeholder = repo.commit(
+        None,
+        &first_commit.author(),
+        &first_commit.committer(),
+        "codasai: placeholder",
+        &tree,
+        &[],
+    )?;
+
+    Ok(placeholder)
+}
+
+/// Checks out the page of `rev` without moving the branch.
+fn checkout(repo: &Repository, rev: Oid) -> Result<()> {
+    let commit = repo.find_commit(rev)?;
+    repo.checkout_tree(commit.as_object(), Some(CheckoutBuilder::new().safe()))
+        .context("failed to check out the page")?;
+    repo.set_head_detached(rev)?;
+
+    Ok(())
+}
+
+/// Makes `.codasai/rev.toml` in the working tree point to the page of
+/// `commit`. A cherry-pick can't do it on its own when the pages are
+/// reordered.
+fn write_rev_toml(repo: &Repository, commit: &Commit) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .context("bare repositories are not supported")?;
+
+    let entry = commit.tree()?.get_path(Path::new(REV_TOML_PATH))?;
+    let blob = repo.find_blob(entry.id())?;
+    let rev_toml_path = workdir.join(REV_TOML_PATH);
+
+    fs::write(&rev_toml_path, blob.content())
+        .with_context(|| format!("failed to write to file {:?}", rev_toml_path))
+}
+
+fn ensure_clean(repo: &Repository) -> Result<()> {
+    let mut status_opts = StatusOptions::new();
+    status_opts.include_untracked(true);
+
+    let statuses = repo
+        .statuses(Some(&mut status_opts))
+        .context("failed to get status of repository")?;
+
+    let has_changes = statuses
+        .iter()
+        .filter_map(|status| status.path().map(PathBuf::from))
+        .any(|path| !is_build_output(&path));
+
+    if has_changes {
+        bail!("you have unsaved changes. Save or discard them before editing a page.");
+    }
+
+    Ok(())
+}
+
+/// Fails if a file that had conflicts still has conflict markers. The files
+/// are staged anyway, so this is what tells whether they were resolved.
+fn ensure_conflicts_resolved(repo: &Repository) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .context("bare repositories are not supported")?;
+
+    for path in conflicting_paths(repo)? {
+        let content = fs::read_to_string(workdir.join(&path)).unwrap_or_default();
+
+        if content.lines().any(|line| line.starts_with("<<<<<<<")) {
+            bail!(
+                "{:?} still has conflicts. Resolve them and run `codasai page continue` again.",
+                path
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn conflicting_paths(repo: &Repository) -> Result<Vec<String>> {
+    let index = repo.index()?;
+    let mut paths = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+
+        if let Some(entry) = entry {
+            paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+
+    Ok(paths)
+}
+
+fn read_file(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("failed to write to file {:?}", path))
+}
+
+fn to_strings(revs: &[Oid]) -> Vec<String> {
+    revs.iter().map(Oid::to_string).collect()
+}
@@ -1,22 +1,116 @@
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use codasai_server::{Server, SharedState};
+use codasai_server::{Reloader, Server, SharedState};
+use git2::Repository;
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
 
+use super::build::guide_from_git;
 use crate::opts::ServeOpts;
 use crate::util::path;
 
-pub fn serve(_opts: &ServeOpts) -> Result<()> {
+/// Time to wait for the file system to settle down before rebuilding the
+/// guide. Saving a page touches many files inside `.git` at once.
+const WATCH_DELAY: Duration = Duration::from_millis(500);
+
+pub fn serve(opts: &ServeOpts) -> Result<()> {
     let dotcodasai = path::dotcodasai()?;
-    let guide_json = find_guide_json(&dotcodasai)?;
-    Server::new(SharedState::new(guide_json)).launch();
+    let repo_path = dotcodasai
+        .parent()
+        .context("failed to find the guide directory")?
+        .to_path_buf();
+
+    let guide_json = build_guide_json(&repo_path, opts.drafts)?;
+    let state = SharedState::new(guide_json);
+    let server = Server::new(state.clone());
+
+    watch_guide(repo_path, opts.drafts, state, server.reloader())?;
+    server.launch();
+
+    Ok(())
+}
+
+fn build_guide_json(repo_path: &Path, drafts: bool) -> Result<String> {
+    let guide = guide_from_git(repo_path, drafts)?;
+    serde_json::to_string(&guide).context("failed to serialize guide")
+}
+
+/// Watches the guide repository in a background thread, rebuilding the guide
+/// and notifying the clients every time it changes.
+fn watch_guide(
+    repo_path: PathBuf, drafts: bool, state: SharedState<String>, reloader: Reloader,
+) -> Result<()> {
+    let (sender, receiver) = channel();
+    let mut watcher =
+        notify::watcher(sender, WATCH_DELAY).context("failed to create file watcher")?;
+    watcher
+        .watch(&repo_path, RecursiveMode::Recursive)
+        .with_context(|| format!("failed to watch {:?}", repo_path))?;
+
+    let repo = Repository::open(&repo_path)
+        .with_context(|| format!("failed to open git repository at {:?}", repo_path))?;
+
+    thread::spawn(move || {
+        // The watcher stops watching when dropped
+        let _watcher = watcher;
+
+        for event in receiver {
+            if !should_rebuild(&repo, &repo_path, &event) {
+                continue;
+            }
+
+            match build_guide_json(&repo_path, drafts) {
+                Ok(guide_json) => {
+                    if guide_json != state.get() {
+                        state.replace(guide_json);
+                        reloader.notify();
+                    }
+                },
+                Err(e) => eprintln!("Error: failed to rebuild guide: {:?}", e),
+            }
+        }
+    });
 
     Ok(())
 }
 
-fn find_guide_json(dotcodasai: impl AsRef<Path>) -> Result<String> {
-    let guide_json_path = dotcodasai.as_ref().join("out/guide.json");
-    fs::read_to_string(&guide_json_path)
-        .with_context(|| format!("failed to read guide.json at {:?}", guide_json_path))
+fn should_rebuild(repo: &Repository, repo_path: &Path, event: &DebouncedEvent) -> bool {
+    match event {
+        DebouncedEvent::Create(path)
+        | DebouncedEvent::Write(path)
+        | DebouncedEvent::Remove(path) => is_guide_path(repo, repo_path, path),
+        DebouncedEvent::Rename(old_path, new_path) => {
+            is_guide_path(repo, repo_path, old_path) || is_guide_path(repo, repo_path, new_path)
+        },
+        DebouncedEvent::Rescan => true,
+        DebouncedEvent::Error(e, _) => {
+            eprintln!("Error: failed to watch guide: {}", e);
+            false
+        },
+        DebouncedEvent::NoticeWrite(_)
+        | DebouncedEvent::NoticeRemove(_)
+        | DebouncedEvent::Chmod(_) => false,
+    }
+}
+
+/// Returns whether a change to `path` can affect the guide. That is, it is not
+/// part of the build output and it is not ignored by git.
+fn is_guide_path(repo: &Repository, repo_path: &Path, path: &Path) -> bool {
+    let relative_path = match path.strip_prefix(repo_path) {
+        Ok(relative_path) => relative_path,
+        Err(_) => return false,
+    };
+
+    if relative_path.starts_with(".codasai/out") {
+        return false;
+    }
+
+    if relative_path.starts_with(".git") {
+        return true;
+    }
+
+    !repo.is_path_ignored(relative_path).unwrap_or(false)
 }
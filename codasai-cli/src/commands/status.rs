@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use git2::{Delta, DiffOptions, Repository, Status};
+
+use super::build::{revisions, title_of_page};
+use super::page::{is_editing, open_repository};
+use crate::config::{GuideConfig, PageConfig};
+use crate::util;
+
+/// The state of the page that `.codasai/rev.toml` points to.
+enum PageState {
+    Missing,
+    New,
+    Modified,
+    Saved,
+}
+
+/// A file of the code that changed since the last saved page.
+struct CodeChange {
+    status: &'static str,
+    path: String,
+}
+
+pub fn status() -> Result<()> {
+    let repo = open_repository()?;
+    let config = GuideConfig::load()?;
+
+    let rev_toml_path = util::path::dotcodasai()?.join("rev.toml");
+    let rev_toml_string = fs::read_to_string(&rev_toml_path)
+        .with_context(|| format!("failed to read {:?}", rev_toml_path))?;
+    let rev_config = PageConfig::from_str(&rev_toml_string)?;
+
+    let saved_pages = match repo.head() {
+        Ok(_) => revisions(&repo)?.len(),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => 0,
+        Err(e) => return Err(e).context("failed to find the last saved page"),
+    };
+    println!("Saved pages: {}", saved_pages);
+
+    if is_editing(&repo)? {
+        println!(
+            "A saved page is being edited. Run `codasai page continue` to save the changes or \
+             `codasai page abort` to discard them."
+        );
+    }
+
+    let mut warnings = Vec::new();
+    let page_state = if rev_config.page_path.as_os_str().is_empty() {
+        println!("No page has been created yet. Run `codasai page new <title>` to start one.");
+        None
+    } else {
+        let page_state = page_state(&repo, &rev_config.page_path)?;
+        print_page(&rev_config.page_path, &page_state)?;
+        if let PageState::Missing = page_state {
+            warnings.push("the current page does not exist".to_string());
+        }
+        Some(page_state)
+    };
+
+    let changes = code_changes(&config, &repo)?;
+    println!();
+    if changes.is_empty() {
+        println!("No code changed since the last saved page.");
+    } else {
+        println!("Code changed since the last saved page:");
+        let status_width = changes
+            .iter()
+            .map(|change| change.status.len() + 1)
+            .max()
+            .unwrap_or(0);
+        for change in &changes {
+            println!(
+                "    {:<status_width$} {}",
+                format!("{}:", change.status),
+                change.path,
+                status_width = status_width,
+            );
+        }
+    }
+
+    match page_state {
+        Some(PageState::New | PageState::Modified) if changes.is_empty() => {
+            warnings.push("the current page does not change any code".to_string());
+        },
+        Some(PageState::Saved) if !changes.is_empty() => warnings.push(
+            "the code changed but the current page is already saved. Run `codasai page new \
+             <title>` to explain the changes in a new page."
+                .to_string(),
+        ),
+        _ => {},
+    }
+
+    if !warnings.is_empty() {
+        println!();
+        for warning in warnings {
+            println!("warning: {}", warning);
+        }
+    }
+
+    Ok(())
+}
+
+fn page_state(repo: &Repository, page_path: &Path) -> Result<PageState> {
+    if !page_path.exists() {
+        return Ok(PageState::Missing);
+    }
+
+    let status = repo
+        .status_file(page_path)
+        .with_context(|| format!("failed to get the status of {:?}", page_path))?;
+
+    Ok(if status.intersects(Status::WT_NEW | Status::INDEX_NEW) {
+        PageState::New
+    } else if status.is_empty() || status == Status::IGNORED {
+        PageState::Saved
+    } else {
+        PageState::Modified
+    })
+}
+
+fn print_page(page_path: &Path, page_state: &PageState) -> Result<()> {
+    if let PageState::Missing = page_state {
+        println!("Current page: {}", page_path.display());
+    } else {
+        let page = fs::read_to_string(page_path)
+            .with_context(|| format!("failed to read page at {:?}", page_path))?;
+        let title = title_of_page(page_path, &page)?;
+        println!("Current page: {} ({})", title, page_path.display());
+    }
+
+    println!(
+        "{}",
+        match page_state {
+            PageState::Missing => "The page does not exist.",
+            PageState::New => "The page has not been saved yet.",
+            PageState::Modified => "The page has unsaved changes.",
+            PageState::Saved => "The page is saved.",
+        }
+    );
+
+    Ok(())
+}
+
+/// Returns the files of the code that changed in the working tree since the
+/// last saved page. Like when building the guide, the files in `.codasai/` and
+/// in the pages path aren't part of the code.
+fn code_changes(config: &GuideConfig, repo: &Repository) -> Result<Vec<CodeChange>> {
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => None,
+        Err(e) => return Err(e).context("failed to find the last saved page"),
+    };
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .include_untracked(true)
+        .recurse_untracked_dirs(true);
+    let mut diff = repo
+        .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_opts))
+        .context("failed to diff the working tree")?;
+    diff.find_similar(None)?;
+
+    let is_code_file =
+        |path: &PathBuf| !path.starts_with(".codasai/") && !path.starts_with(&config.pages_path);
+
+    let mut changes = Vec::new();
+    for delta in diff.deltas() {
+        let old_path = delta.old_file().path().map(Path::to_path_buf);
+        let new_path = delta.new_file().path().map(Path::to_path_buf);
+        if !old_path.iter().chain(&new_path).any(is_code_file) {
+            continue;
+        }
+
+        let path = new_path.or(old_path).unwrap_or_default();
+        let (status, path) = match delta.status() {
+            Delta::Added | Delta::Untracked | Delta::Copied => {
+                ("added", path.display().to_string())
+            },
+            Delta::Deleted => ("deleted", path.display().to_string()),
+            Delta::Renamed => {
+                let old_path = delta.old_file().path().unwrap_or(&path);
+                (
+                    "renamed",
+                    format!("{} -> {}", old_path.display(), path.display()),
+                )
+            },
+            Delta::Modified | Delta::Typechange => ("modified", path.display().to_string()),
+            _ => continue,
+        };
+        changes.push(CodeChange { status, path });
+    }
+
+    Ok(changes)
+}
@@ -1,5 +1,5 @@
 mod guide;
 mod page;
 
-pub use guide::GuideConfig;
+pub use guide::{default_pages_path, ChapterConfig, GuideConfig};
 pub use page::PageConfig;
@@ -1,13 +1,31 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use serde::Deserialize;
 
+use crate::util;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct GuideConfig {
     pub title: String,
     #[serde(default = "default_pages_path")]
     pub pages_path: PathBuf,
+    /// Overrides the syntax used to highlight files. The keys are paths, file
+    /// names or extensions (as in `*.ext`) and the values are syntax names
+    /// (e.g. `Rust` or `rs`).
+    #[serde(default)]
+    pub syntaxes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub chapters: Vec<ChapterConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChapterConfig {
+    pub title: String,
+    /// The first page of the chapter, either its path or its name inside
+    /// `pages_path` without the extension (e.g. `introduction`).
+    pub start: String,
 }
 
 impl GuideConfig {
@@ -19,8 +37,13 @@ impl GuideConfig {
         toml::de::from_str(&config_str)
             .with_context(|| format!("failed to process config at {:?}", path))
     }
+
+    /// Loads `guide.toml` from the `.codasai` directory of the current guide.
+    pub fn load() -> Result<Self> {
+        Self::from_file(util::path::dotcodasai()?.join("guide.toml"))
+    }
 }
 
-fn default_pages_path() -> PathBuf {
+pub fn default_pages_path() -> PathBuf {
     PathBuf::from("_pages/")
 }
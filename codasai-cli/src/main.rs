@@ -13,8 +13,11 @@ fn main() -> Result<()> {
     match opts {
         CliOpts::Init(opts) => commands::init(&opts)?,
         CliOpts::Build(opts) => commands::build(&opts)?,
+        CliOpts::Check(opts) => commands::check(&opts)?,
+        CliOpts::Export(opts) => commands::export(&opts)?,
         CliOpts::Serve(opts) => commands::serve(&opts)?,
         CliOpts::Page(opts) => commands::page(&opts)?,
+        CliOpts::Status => commands::status()?,
     }
 
     Ok(())
@@ -13,20 +13,71 @@ pub enum CliOpts {
     /// app.
     Build(BuildOpts),
 
+    /// Checks that the files and lines referenced by the pages exist.
+    Check(CheckOpts),
+
+    /// Exports the guide so it can be read without running `codasai serve`.
+    Export(ExportOpts),
+
     Page(PageOpts),
 
+    /// Shows the current page, whether it's saved and the code that changed
+    /// since the last saved page.
+    Status,
+
+    /// Serves the guide with the web app, rebuilding it every time the
+    /// repository changes.
     Serve(ServeOpts),
 }
 
 #[derive(Debug, StructOpt)]
 pub struct InitOpts {
     pub title: String,
+
+    /// The directory where the pages are stored, relative to the root of the
+    /// guide. Defaults to `_pages`.
+    #[structopt(long)]
+    pub pages_path: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
 pub struct BuildOpts {
     #[structopt(default_value = ".")]
     pub guide: PathBuf,
+
+    /// Adds the unsaved page and code as a draft at the end of the guide.
+    #[structopt(long)]
+    pub drafts: bool,
+
+    /// Fails instead of warning when a page references a file or lines that
+    /// don't exist.
+    #[structopt(long)]
+    pub strict: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct CheckOpts {
+    #[structopt(default_value = ".")]
+    pub guide: PathBuf,
+
+    /// Also checks the unsaved page.
+    #[structopt(long)]
+    pub drafts: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ExportOpts {
+    #[structopt(default_value = ".")]
+    pub guide: PathBuf,
+
+    /// Exports the guide as a static site to the given directory.
+    #[structopt(long)]
+    pub html: Option<PathBuf>,
+
+    /// Exports the guide as a single HTML file that can be opened without a
+    /// server.
+    #[structopt(long)]
+    pub single_file: Option<PathBuf>,
 }
 
 #[derive(Debug, StructOpt)]
@@ -39,6 +90,33 @@ pub struct PageOpts {
 pub enum PageSubcmd {
     New(PageNewOpts),
     Save(PageSaveOpts),
+
+    /// Lists the saved pages in the order they appear in the guide.
+    List(PageListOpts),
+
+    /// Checks out a saved page so it and its code can be changed.
+    Edit(PageEditOpts),
+
+    /// Inserts a new page after a saved one and checks it out so it can be
+    /// written.
+    Insert(PageInsertOpts),
+
+    /// Moves a saved page after another one.
+    Move(PageMoveOpts),
+
+    /// Merges a saved page into the page before it. The text of the page is
+    /// added at the end of the page before it, without its front matter.
+    Squash(PageSquashOpts),
+
+    /// Splits a saved page in two, the second one being a new page.
+    Split(PageSplitOpts),
+
+    /// Saves the changes to the page being edited and replays the pages after
+    /// it on top of them.
+    Continue,
+
+    /// Stops editing a page, discarding the changes.
+    Abort,
 }
 
 #[derive(Debug, StructOpt)]
@@ -53,7 +131,70 @@ pub struct PageSaveOpts {
 }
 
 #[derive(Debug, StructOpt)]
-pub struct ServeOpts {}
+pub struct PageListOpts {
+    /// Prints the pages as JSON.
+    #[structopt(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PageEditOpts {
+    /// The number of the page, as shown by `page list`, or its id.
+    pub page: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PageInsertOpts {
+    /// The number or the id of the page the new page goes after.
+    #[structopt(long)]
+    pub after: String,
+
+    pub title: String,
+
+    /// The message of the commit of the new page. Defaults to its title.
+    #[structopt(short, long)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PageMoveOpts {
+    /// The number of the page, as shown by `page list`, or its id.
+    pub page: String,
+
+    /// The number or the id of the page it goes after.
+    #[structopt(long, required_unless = "before", conflicts_with = "before")]
+    pub after: Option<String>,
+
+    /// The number or the id of the page it goes before.
+    #[structopt(long)]
+    pub before: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PageSquashOpts {
+    /// The number of the page, as shown by `page list`, or its id.
+    pub page: String,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct PageSplitOpts {
+    /// The number of the page, as shown by `page list`, or its id.
+    pub page: String,
+
+    /// The title of the new page.
+    pub title: String,
+
+    /// The message of the commit of the new page. Defaults to its title.
+    #[structopt(short, long)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+pub struct ServeOpts {
+    /// Adds the unsaved page and code as a draft at the end of the guide.
+    #[structopt(long)]
+    pub drafts: bool,
+}
 
 impl CliOpts {
     pub fn from_args() -> Self {
@@ -33,3 +33,160 @@ pub mod path {
         dotcodasai.ok_or_else(|| anyhow!("failed to find \".codasai\" directory"))
     }
 }
+
+pub mod git {
+    use std::env;
+
+    use anyhow::{anyhow, Context, Result};
+    use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone};
+    use git2::{Repository, Signature, Time};
+
+    /// Returns the author and the committer of a new commit. Like git, they are
+    /// taken from the `GIT_AUTHOR_*` and `GIT_COMMITTER_*` environment
+    /// variables if set or from the git config otherwise.
+    pub fn signatures(repo: &Repository) -> Result<(Signature<'static>, Signature<'static>)> {
+        Ok((signature(repo, "AUTHOR")?, signature(repo, "COMMITTER")?))
+    }
+
+    fn signature(repo: &Repository, role: &str) -> Result<Signature<'static>> {
+        let config_signature = repo.signature().ok();
+
+        let name = env::var(format!("GIT_{}_NAME", role))
+            .ok()
+            .or_else(|| config_signature.as_ref()?.name().map(str::to_string))
+            .context("failed to find your name. Set it with `git config user.name`")?;
+        let email = env::var(format!("GIT_{}_EMAIL", role))
+            .ok()
+            .or_else(|| config_signature.as_ref()?.email().map(str::to_string))
+            .context("failed to find your email. Set it with `git config user.email`")?;
+        let time = env::var(format!("GIT_{}_DATE", role))
+            .ok()
+            .map(|date| {
+                parse_date(&date)
+                    .with_context(|| format!("failed to parse GIT_{}_DATE={:?}", role, date))
+            })
+            .transpose()?;
+
+        let signature = match time {
+            Some(time) => Signature::new(&name, &email, &time),
+            None => Signature::now(&name, &email),
+        };
+
+        signature.context("failed to create the signature of the commit")
+    }
+
+    /// Parses a date in one of the formats that git accepts: its internal
+    /// format (e.g. `1609459200 +0100`), RFC 2822 (e.g. `Fri, 1 Jan 2021
+    /// 00:00:00 +0000`) and ISO 8601 (e.g. `2021-01-01T00:00:00Z`). Unlike
+    /// git, dates without a timezone are in UTC rather than in the local
+    /// timezone, so that a page saved with the same date gets the same commit
+    /// on every machine.
+    fn parse_date(date: &str) -> Result<Time> {
+        let date = date.trim();
+
+        if let Some(time) = parse_internal_date(date) {
+            return Ok(time);
+        }
+
+        let date_time = DateTime::parse_from_rfc2822(date)
+            .or_else(|_| DateTime::parse_from_rfc3339(date))
+            .or_else(|_| DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S %z"))
+            .or_else(|_| DateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%z"))
+            .ok()
+            .or_else(|| parse_utc_date(date))
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown date format. Use a format like `2021-01-01T00:00:00Z`, `Fri, 1 Jan \
+                     2021 00:00:00 +0000` or `1609459200 +0000`"
+                )
+            })?;
+
+        Ok(Time::new(
+            date_time.timestamp(),
+            date_time.offset().local_minus_utc() / 60,
+        ))
+    }
+
+    /// Parses a date in the internal format of git, e.g. `1609459200 +0100`.
+    fn parse_internal_date(date: &str) -> Option<Time> {
+        let (seconds, offset) = date.trim_start_matches('@').split_once(' ')?;
+        let seconds = seconds.parse::<i64>().ok()?;
+
+        let (sign, offset) = match offset.strip_prefix('-') {
+            Some(offset) => (-1, offset),
+            None => (1, offset.trim_start_matches('+')),
+        };
+        if offset.len() != 4 {
+            return None;
+        }
+        let hours = offset[..2].parse::<i32>().ok()?;
+        let minutes = offset[2..].parse::<i32>().ok()?;
+
+        Some(Time::new(seconds, sign * (hours * 60 + minutes)))
+    }
+
+    /// Parses an ISO 8601 date without a timezone as a UTC date.
+    fn parse_utc_date(date: &str) -> Option<DateTime<FixedOffset>> {
+        let naive = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S"))
+            .ok()?;
+
+        Some(FixedOffset::east(0).from_utc_datetime(&naive))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// 2021-01-01T00:00:00Z
+        const NEW_YEAR: i64 = 1609459200;
+
+        fn parse(date: &str) -> (i64, i32) {
+            let time = parse_date(date).unwrap();
+            (time.seconds(), time.offset_minutes())
+        }
+
+        #[test]
+        fn test_parse_internal_date() {
+            assert_eq!(parse("1609459200 +0000"), (NEW_YEAR, 0));
+            assert_eq!(parse("1609459200 +0100"), (NEW_YEAR, 60));
+            assert_eq!(parse("@1609459200 -0230"), (NEW_YEAR, -150));
+        }
+
+        #[test]
+        fn test_parse_rfc2822_date() {
+            assert_eq!(parse("Fri, 1 Jan 2021 00:00:00 +0000"), (NEW_YEAR, 0));
+            assert_eq!(parse("Fri, 1 Jan 2021 01:00:00 +0100"), (NEW_YEAR, 60));
+        }
+
+        #[test]
+        fn test_parse_iso8601_date() {
+            assert_eq!(parse("2021-01-01T00:00:00Z"), (NEW_YEAR, 0));
+            assert_eq!(parse("2021-01-01T01:00:00+01:00"), (NEW_YEAR, 60));
+            assert_eq!(parse("2021-01-01 01:00:00 +0100"), (NEW_YEAR, 60));
+            assert_eq!(parse("2020-12-31T23:00:00-0100"), (NEW_YEAR, -60));
+        }
+
+        #[test]
+        fn test_parse_date_without_timezone() {
+            assert_eq!(parse("2021-01-01T00:00:00"), (NEW_YEAR, 0));
+            assert_eq!(parse(" 2021-01-01 00:00:00\n"), (NEW_YEAR, 0));
+        }
+
+        #[test]
+        fn test_parse_unknown_date() {
+            for date in &[
+                "",
+                "yesterday",
+                "1609459200",
+                "1609459200 +1",
+                "1609459200 +01:00",
+                "2021-01-01",
+                "2021-13-01T00:00:00Z",
+                "2021-01-01T00:00:00 UTC",
+            ] {
+                assert!(parse_date(date).is_err(), "{:?}", date);
+            }
+        }
+    }
+}
@@ -1,34 +1,51 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use include_dir::{include_dir, Dir};
 use rocket::response::content::{Html, Json};
-use rocket::{State, get, routes};
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{State, Shutdown, get, routes};
 
-use crate::file::EmbeddedFile;
+pub use crate::file::EmbeddedFile;
+pub use crate::reload::Reloader;
 pub use crate::state::SharedState;
 
 mod file;
+mod reload;
 mod state;
 
 const PUBLIC_DIR: Dir = include_dir!("./dist/");
+const INDEX_HTML: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/dist/index.html"));
 
 pub type GuideJson = String;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Server {
     state: SharedState<GuideJson>,
+    reloader: Reloader,
 }
 
 impl Server {
     pub fn new(state: SharedState<GuideJson>) -> Self {
-        Self { state }
+        Self {
+            state,
+            reloader: Reloader::new(),
+        }
+    }
+
+    /// Returns a handle that can be used to tell the open browser tabs to
+    /// fetch the guide again.
+    pub fn reloader(&self) -> Reloader {
+        self.reloader.clone()
     }
 
     pub fn launch(self) {
         rocket::async_main(async move {
             rocket::build()
-                .mount("/", routes![index, public, get_guide])
+                .mount("/", routes![index, page, public, get_guide, events])
                 .manage(self.state)
+                .manage(self.reloader)
                 .ignite()
                 .await?
                 .launch()
@@ -38,11 +55,59 @@ impl Server {
     }
 }
 
+/// Returns the page that loads the guide from a `guide.js` file next to it
+/// instead of requesting it to the server. The page expects the public files to
+/// be in a `public/` directory next to it too.
+pub fn export_html() -> &'static str {
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/dist/export.html"))
+}
+
+/// Returns the template of a page that has the web app and the guide inlined.
+/// It has the following placeholders:
+///
+/// - `{{ markdown_css }}` and `{{ style_css }}`: the public stylesheets
+/// - `{{ guide }}`: a JavaScript string with the guide encoded as JSON
+/// - `{{ wasm_glue }}`: a JavaScript string with the glue code of the web app
+/// - `{{ wasm_module }}`: a JavaScript string with the web app module encoded
+///   in base64
+pub fn single_file_html() -> &'static str {
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/dist/single-file.html"))
+}
+
+/// Returns the file served at `/public/<path>`.
+pub fn public_file(path: impl AsRef<Path>) -> Option<EmbeddedFile> {
+    PUBLIC_DIR
+        .get_file(path)
+        .map(|file| EmbeddedFile(file.path(), file.contents()))
+}
+
+/// Returns all the files served under `/public/`. The paths are relative to the
+/// public directory.
+pub fn public_files() -> Vec<EmbeddedFile> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PUBLIC_DIR];
+
+    while let Some(dir) = dirs.pop() {
+        files.extend(
+            dir.files()
+                .iter()
+                .map(|file| EmbeddedFile(file.path(), file.contents())),
+        );
+        dirs.extend(dir.dirs());
+    }
+
+    files
+}
+
 #[get("/")]
 fn index() -> Html<&'static str> {
-    let index_html = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/dist/index.html"));
+    Html(INDEX_HTML)
+}
 
-    Html(index_html)
+/// Serves the web app for links to a page, which it reads from the URL.
+#[get("/page/<_route..>")]
+fn page(_route: PathBuf) -> Html<&'static str> {
+    Html(INDEX_HTML)
 }
 
 #[get("/guide")]
@@ -50,11 +115,25 @@ fn get_guide(guide: &State<SharedState<GuideJson>>) -> Json<String> {
     Json(guide.inner().get())
 }
 
+#[get("/events")]
+fn events(reloader: &State<Reloader>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut reload_receiver = reloader.subscribe();
+
+    EventStream! {
+        loop {
+            select! {
+                message = reload_receiver.recv() => match message {
+                    // If we lagged behind, the guide still changed so the client must reload
+                    Ok(()) | Err(RecvError::Lagged(_)) => yield Event::data("reload"),
+                    Err(RecvError::Closed) => break,
+                },
+                _ = &mut shutdown => break,
+            }
+        }
+    }
+}
+
 #[get("/public/<path..>")]
 fn public(path: PathBuf) -> Option<EmbeddedFile> {
-    if let Some(file) = PUBLIC_DIR.get_file(&path) {
-        Some(EmbeddedFile(file.path(), file.contents()))
-    } else {
-        None
-    }
+    public_file(path)
 }
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use rocket::tokio::sync::broadcast::{self, Receiver, Sender};
+
+/// Broadcasts reload notifications to every client listening on `/events`.
+#[derive(Debug, Clone)]
+pub struct Reloader(Arc<Sender<()>>);
+
+impl Reloader {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(16);
+        Self(Arc::new(sender))
+    }
+
+    /// Notifies all the subscribed clients that the guide has changed.
+    pub fn notify(&self) {
+        // Sending only fails when there are no subscribers, which just means no
+        // browser tab is open.
+        let _ = self.0.send(());
+    }
+
+    pub fn subscribe(&self) -> Receiver<()> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for Reloader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for Reloader {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Reloader {}
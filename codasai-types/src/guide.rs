@@ -6,12 +6,18 @@ use crate::Vfs;
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Guide {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapters: Vec<GuideChapter>,
     pub vfs: Vfs,
 }
 
 impl Guide {
     pub fn new(name: String, vfs: Vfs) -> Self {
-        Self { name, vfs }
+        Self {
+            name,
+            chapters: Vec::new(),
+            vfs,
+        }
     }
 
     pub fn from_json(s: &str) -> Result<Self> {
@@ -20,3 +26,12 @@ impl Guide {
         Ok(this)
     }
 }
+
+/// A group of consecutive pages. `pages` are indexes into the snapshots of the
+/// guide. Pages before the first chapter are grouped in a chapter without a
+/// title.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GuideChapter {
+    pub title: String,
+    pub pages: Vec<usize>,
+}
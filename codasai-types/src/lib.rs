@@ -1,8 +1,11 @@
 pub mod guide;
+pub mod page;
 pub mod vfs;
 
-pub use guide::Guide;
+pub use guide::{Guide, GuideChapter};
+pub use page::PageMetadata;
 pub use vfs::{
-    Vfs, VfsDirectory, VfsDirectoryOrFile, VfsFile, VfsFilesHandle, VfsPath, VfsRoot, VfsSnapshot,
+    Vfs, VfsBinary, VfsChange, VfsChangeKind, VfsDelta, VfsDeltaOp, VfsDirectory,
+    VfsDirectoryOrFile, VfsFile, VfsFilesHandle, VfsPath, VfsRoot, VfsSnapshot, VfsToken,
     VfsWalker, VfsWalkerEntry,
 };
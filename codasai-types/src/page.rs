@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Information about a page that is set in its front matter.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PageMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Estimated time to read the page in minutes. Pages that are read in less
+    /// than half a minute don't have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reading_time: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub authors: Vec<String>,
+    /// Any other key of the front matter.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+impl PageMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
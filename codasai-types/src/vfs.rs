@@ -1,13 +1,17 @@
 use serde::{Deserialize, Serialize};
 
+mod change;
+mod delta;
 mod directory;
 mod file;
 mod path;
 mod snapshot;
 mod walker;
 
+pub use change::{VfsChange, VfsChangeKind};
+pub use delta::{VfsDelta, VfsDeltaOp};
 pub use directory::{VfsDirectory, VfsRoot};
-pub use file::{VfsFile, VfsFilesHandle};
+pub use file::{VfsBinary, VfsFile, VfsFilesHandle, VfsToken};
 pub use path::VfsPath;
 pub use snapshot::VfsSnapshot;
 pub use walker::{VfsDirectoryOrFile, VfsWalker, VfsWalkerEntry};
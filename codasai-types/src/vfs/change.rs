@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use super::path::VfsPath;
+
+/// A file that changed in a snapshot compared to the snapshot before it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VfsChange {
+    pub kind: VfsChangeKind,
+    pub path: VfsPath,
+    /// The path of the file before it was renamed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<VfsPath>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VfsChangeKind {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl VfsChange {
+    pub fn new(kind: VfsChangeKind, path: VfsPath) -> Self {
+        Self {
+            kind,
+            path,
+            old_path: None,
+        }
+    }
+
+    pub fn renamed(old_path: VfsPath, path: VfsPath) -> Self {
+        Self {
+            kind: VfsChangeKind::Renamed,
+            path,
+            old_path: Some(old_path),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::file::VfsToken;
+
+/// Minimum number of lines that have to match to be copied from the base file
+/// when they are surrounded by changes. Shorter matches are inserted instead
+/// because a copy is not much smaller than a few lines.
+const MIN_COPY_LEN: usize = 3;
+
+/// The content of a file stored as the changes to the lines of another file,
+/// the base.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VfsDelta {
+    pub base: usize,
+    pub ops: Vec<VfsDeltaOp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VfsDeltaOp {
+    /// Copies `len` lines starting at line `start` of the base file.
+    Copy(usize, usize),
+    /// Inserts new lines with their syntax highlighting.
+    Insert {
+        lines: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        highlights: Vec<Vec<VfsToken>>,
+    },
+}
+
+/// The whole content of a text file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct TextContent {
+    pub content: String,
+    pub highlights: Vec<Vec<VfsToken>>,
+}
+
+impl TextContent {
+    fn lines(&self) -> Vec<Line<'_>> {
+        self.content
+            .split('\n')
+            .enumerate()
+            .map(|(line_index, text)| Line {
+                text,
+                tokens: self
+                    .highlights
+                    .get(line_index)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+}
+
+/// A line is only equal to another if both the text and the highlighting are
+/// equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Line<'a> {
+    text: &'a str,
+    tokens: &'a [VfsToken],
+}
+
+impl VfsDelta {
+    /// Computes the changes that turn `base_content` into `content`. The
+    /// changes are not minimal: the lines that are not part of the common
+    /// prefix or suffix are matched greedily.
+    pub(super) fn new(base: usize, base_content: &TextContent, content: &TextContent) -> Self {
+        let base_lines = base_content.lines();
+        let lines = content.lines();
+
+        let prefix_len = base_lines
+            .iter()
+            .zip(&lines)
+            .take_while(|(base_line, line)| base_line == line)
+            .count();
+        let max_suffix_len = base_lines.len().min(lines.len()) - prefix_len;
+        let suffix_len = base_lines
+            .iter()
+            .rev()
+            .zip(lines.iter().rev())
+            .take(max_suffix_len)
+            .take_while(|(base_line, line)| base_line == line)
+            .count();
+
+        let base_middle = prefix_len..base_lines.len() - suffix_len;
+        let middle = prefix_len..lines.len() - suffix_len;
+
+        let mut base_positions: HashMap<Line, Vec<usize>> = HashMap::new();
+        for position in base_middle.clone() {
+            base_positions
+                .entry(base_lines[position])
+                .or_default()
+                .push(position);
+        }
+
+        let mut builder = DeltaBuilder::new(base);
+        builder.copy(0, prefix_len);
+
+        let mut base_cursor = base_middle.start;
+        let mut line_index = middle.start;
+        while line_index < middle.end {
+            let longest_match = base_positions
+                .get(&lines[line_index])
+                .into_iter()
+                .flatten()
+                .filter(|&&position| position >= base_cursor)
+                .map(|&position| {
+                    let len = (position..base_middle.end)
+                        .zip(line_index..middle.end)
+                        .take_while(|&(base_index, index)| base_lines[base_index] == lines[index])
+                        .count();
+
+                    (position, len)
+                })
+                .max_by_key(|&(_, len)| len);
+
+            match longest_match {
+                Some((position, len)) if len >= MIN_COPY_LEN || line_index + len == middle.end => {
+                    builder.copy(position, len);
+                    base_cursor = position + len;
+                    line_index += len;
+                },
+                _ => {
+                    builder.insert(lines[line_index]);
+                    line_index += 1;
+                },
+            }
+        }
+
+        builder.copy(base_middle.end, suffix_len);
+        builder.finish()
+    }
+
+    /// Applies the changes to `base_content`.
+    pub(super) fn apply(&self, base_content: &TextContent) -> TextContent {
+        let base_lines = base_content.lines();
+
+        let mut lines = Vec::new();
+        let mut highlights = Vec::new();
+
+        for op in &self.ops {
+            match op {
+                VfsDeltaOp::Copy(start, len) => {
+                    for line in base_lines.iter().skip(*start).take(*len) {
+                        lines.push(line.text);
+                        highlights.push(line.tokens.to_vec());
+                    }
+                },
+                VfsDeltaOp::Insert {
+                    lines: new_lines,
+                    highlights: new_highlights,
+                } => {
+                    for (line_index, line) in new_lines.iter().enumerate() {
+                        lines.push(line);
+                        highlights
+                            .push(new_highlights.get(line_index).cloned().unwrap_or_default());
+                    }
+                },
+            }
+        }
+
+        while matches!(highlights.last(), Some(tokens) if tokens.is_empty()) {
+            highlights.pop();
+        }
+
+        TextContent {
+            content: lines.join("\n"),
+            highlights,
+        }
+    }
+}
+
+/// Builds the operations of a delta merging adjacent operations.
+struct DeltaBuilder {
+    base: usize,
+    ops: Vec<VfsDeltaOp>,
+}
+
+impl DeltaBuilder {
+    fn new(base: usize) -> Self {
+        Self {
+            base,
+            ops: Vec::new(),
+        }
+    }
+
+    fn copy(&mut self, start: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        match self.ops.last_mut() {
+            Some(VfsDeltaOp::Copy(last_start, last_len)) if *last_start + *last_len == start => {
+                *last_len += len;
+            },
+            _ => self.ops.push(VfsDeltaOp::Copy(start, len)),
+        }
+    }
+
+    fn insert(&mut self, line: Line) {
+        if !matches!(self.ops.last(), Some(VfsDeltaOp::Insert { .. })) {
+            self.ops.push(VfsDeltaOp::Insert {
+                lines: Vec::new(),
+                highlights: Vec::new(),
+            });
+        }
+
+        if let Some(VfsDeltaOp::Insert { lines, highlights }) = self.ops.last_mut() {
+            if !line.tokens.is_empty() {
+                highlights.resize(lines.len(), Vec::new());
+                highlights.push(line.tokens.to_vec());
+            }
+            lines.push(line.text.to_string());
+        }
+    }
+
+    fn finish(self) -> VfsDelta {
+        VfsDelta {
+            base: self.base,
+            ops: self.ops,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(content: &str) -> TextContent {
+        TextContent {
+            content: content.to_string(),
+            highlights: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let base = text("fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n}\n");
+        let contents = [
+            "fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n}\n",
+            "fn main() {\n    let a = 1;\n    let b = 2;\n    let c = 3;\n    dbg!(a);\n}\n",
+            "use std::io;\n\nfn main() {\n    let b = 2;\n    let c = 3;\n}\n",
+            "",
+            "fn main() {}",
+        ];
+
+        for content in contents.iter().map(|content| text(content)) {
+            let delta = VfsDelta::new(0, &base, &content);
+            assert_eq!(delta.apply(&base), content);
+        }
+    }
+
+    #[test]
+    fn test_delta_appended_lines() {
+        let base = text("a\nb\nc\nd\n");
+        let content = text("a\nb\nc\nd\ne\n");
+
+        insta::assert_ron_snapshot!(VfsDelta::new(0, &base, &content), @r###"
+        VfsDelta(
+          base: 0,
+          ops: [
+            copy(0, 4),
+            insert(
+              lines: [
+                "e",
+              ],
+            ),
+            copy(4, 1),
+          ],
+        )
+        "###);
+    }
+
+    #[test]
+    fn test_delta_keeps_highlights() {
+        let keyword = || vec![VfsToken::new(2, "keyword".into())];
+        let base = TextContent {
+            content: "fn a() {}\nfn b() {}".into(),
+            highlights: vec![keyword(), keyword()],
+        };
+        let content = TextContent {
+            content: "fn a() {}\n// b\nfn b() {}".into(),
+            highlights: vec![
+                keyword(),
+                vec![VfsToken::new(4, "comment".into())],
+                keyword(),
+            ],
+        };
+
+        let delta = VfsDelta::new(0, &base, &content);
+        assert_eq!(delta.apply(&base), content);
+    }
+}
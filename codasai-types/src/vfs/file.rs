@@ -1,33 +1,132 @@
 use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::rc::Rc;
 
 use serde::{Deserialize, Serialize};
 
+use super::delta::{TextContent, VfsDelta};
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct VfsFilesHandle(Rc<RefCell<Vec<VfsFile>>>);
+pub struct VfsFilesHandle(Rc<RefCell<VfsFiles>>);
 
 impl VfsFilesHandle {
     pub fn new() -> Self {
-        Self(Rc::new(RefCell::new(Vec::new())))
+        Self(Rc::new(RefCell::new(VfsFiles::default())))
     }
 
+    /// Adds a file and returns its index. If a file with the same content was
+    /// already added, the index of that file is returned instead.
     pub fn add_file(&self, file: VfsFile) -> usize {
         let mut files = self.0.borrow_mut();
-        files.push(file);
 
-        files.len() - 1
+        if let Some(file_index) = files.find_file(&file) {
+            return file_index;
+        }
+
+        files.files.push(file);
+        let file_index = files.files.len() - 1;
+        files.index_file(file_index);
+
+        file_index
+    }
+
+    /// Adds a file even if a file with the same content was already added, e.g.
+    /// to highlight the same content with another syntax, and returns its
+    /// index.
+    pub fn add_distinct_file(&self, file: VfsFile) -> usize {
+        let mut files = self.0.borrow_mut();
+
+        files.files.push(file);
+        let file_index = files.files.len() - 1;
+        files.index_file(file_index);
+
+        file_index
     }
 
     pub fn write_file(&self, file_index: usize, content: String) {
-        if let Some(file) = self.0.borrow_mut().get_mut(file_index) {
-            file.content = content;
+        let mut files = self.0.borrow_mut();
+
+        if file_index < files.files.len() {
+            files.files[file_index] = VfsFile::new(content);
+            // Files stored as a delta of this file changed too
+            files.invalidate();
         } else {
             tracing::warn!("tried to write into a non-existing file");
         }
     }
 
+    /// Returns the content of a text file. Binary files are read with
+    /// [read_binary](VfsFilesHandle::read_binary).
     pub fn read_file(&self, file_index: usize) -> Option<String> {
-        self.0.borrow().get(file_index).map(|f| f.content.clone())
+        let decoded = self.0.borrow_mut().decode(file_index)?;
+        Some(decoded.content.clone())
+    }
+
+    pub fn read_binary(&self, file_index: usize) -> Option<VfsBinary> {
+        match self.0.borrow().files.get(file_index)? {
+            VfsFile::Binary(binary) => Some(binary.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the syntax highlighting of each line of the file. Lines without
+    /// highlighting may be missing at the end.
+    pub fn read_highlights(&self, file_index: usize) -> Option<Vec<Vec<VfsToken>>> {
+        let decoded = self.0.borrow_mut().decode(file_index)?;
+        Some(decoded.highlights.clone())
+    }
+
+    pub fn set_highlights(&self, file_index: usize, highlights: Vec<Vec<VfsToken>>) {
+        let mut files = self.0.borrow_mut();
+
+        match files.decode(file_index) {
+            Some(decoded) => {
+                files.files[file_index] = VfsFile::Full {
+                    content: decoded.content.clone(),
+                    highlights,
+                };
+                files.invalidate();
+            },
+            None => tracing::warn!("tried to highlight a non-existing file"),
+        }
+    }
+
+    /// Returns the file at `file_index` as it is stored.
+    pub fn file(&self, file_index: usize) -> Option<VfsFile> {
+        self.0.borrow().files.get(file_index).cloned()
+    }
+
+    /// Computes the changes to the lines of the file at `base_index` that
+    /// result in the file at `file_index`.
+    pub fn delta(&self, file_index: usize, base_index: usize) -> Option<VfsDelta> {
+        let mut files = self.0.borrow_mut();
+        let base_content = files.decode(base_index)?;
+        let content = files.decode(file_index)?;
+
+        Some(VfsDelta::new(base_index, &base_content, &content))
+    }
+
+    /// Stores the file at `file_index` as `delta`. The base of the delta must
+    /// not depend on the file at `file_index`.
+    pub fn set_delta(&self, file_index: usize, delta: VfsDelta) {
+        let mut files = self.0.borrow_mut();
+
+        if file_index < files.files.len() {
+            files.files[file_index] = VfsFile::Delta { delta };
+        } else {
+            tracing::warn!("tried to store a non-existing file as a delta");
+        }
+    }
+
+    /// Returns the number of stored files.
+    pub fn len(&self) -> usize {
+        self.0.borrow().files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
@@ -37,13 +136,340 @@ impl Default for VfsFilesHandle {
     }
 }
 
+/// The global array of files. Files are indexed by the hash of their content
+/// so that files with the same content are stored only once.
+///
+/// Files stored as a delta are decoded when they are read and kept decoded.
+/// The index is built when a file is first added so that reading a guide
+/// doesn't decode every file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<VfsFile>", into = "Vec<VfsFile>")]
+struct VfsFiles {
+    files: Vec<VfsFile>,
+    indexes_by_hash: Option<HashMap<u64, Vec<usize>>>,
+    decoded_files: HashMap<usize, Rc<TextContent>>,
+}
+
+impl VfsFiles {
+    /// Finds a file with the same content as `file`. Files stored as a delta
+    /// are never looked up.
+    fn find_file(&mut self, file: &VfsFile) -> Option<usize> {
+        let hash = match file {
+            VfsFile::Full { content, .. } => content_hash(content.as_bytes()),
+            VfsFile::Binary(binary) => content_hash(&binary.data),
+            VfsFile::Delta { .. } => return None,
+        };
+        let indexes = self.indexes_by_hash().get(&hash)?.clone();
+
+        indexes.into_iter().find(|&file_index| match file {
+            VfsFile::Full { content, .. } => {
+                matches!(self.decode(file_index), Some(decoded) if &decoded.content == content)
+            },
+            _ => self.files.get(file_index) == Some(file),
+        })
+    }
+
+    fn index_file(&mut self, file_index: usize) {
+        if let Some(hash) = self.file_hash(file_index) {
+            self.indexes_by_hash()
+                .entry(hash)
+                .or_default()
+                .push(file_index);
+        }
+    }
+
+    fn indexes_by_hash(&mut self) -> &mut HashMap<u64, Vec<usize>> {
+        if self.indexes_by_hash.is_none() {
+            let mut indexes_by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+            for file_index in 0..self.files.len() {
+                if let Some(hash) = self.file_hash(file_index) {
+                    indexes_by_hash.entry(hash).or_default().push(file_index);
+                }
+            }
+
+            self.indexes_by_hash = Some(indexes_by_hash);
+        }
+
+        self.indexes_by_hash.get_or_insert_with(HashMap::new)
+    }
+
+    fn file_hash(&mut self, file_index: usize) -> Option<u64> {
+        if let VfsFile::Binary(binary) = self.files.get(file_index)? {
+            return Some(content_hash(&binary.data));
+        }
+
+        let decoded = self.decode(file_index)?;
+        Some(content_hash(decoded.content.as_bytes()))
+    }
+
+    /// Forgets the decoded files and the index because the content of some
+    /// files changed.
+    fn invalidate(&mut self) {
+        self.indexes_by_hash = None;
+        self.decoded_files.clear();
+    }
+
+    fn decode(&mut self, file_index: usize) -> Option<Rc<TextContent>> {
+        if let Some(decoded) = self.decoded_files.get(&file_index) {
+            return Some(Rc::clone(decoded));
+        }
+
+        let decoded = match self.files.get(file_index)? {
+            VfsFile::Full {
+                content,
+                highlights,
+            } => Rc::new(TextContent {
+                content: content.clone(),
+                highlights: highlights.clone(),
+            }),
+            VfsFile::Delta { delta } => {
+                let delta = delta.clone();
+                let base_content = self.decode(delta.base)?;
+                Rc::new(delta.apply(&base_content))
+            },
+            VfsFile::Binary(_) => return None,
+        };
+
+        self.decoded_files.insert(file_index, Rc::clone(&decoded));
+        Some(decoded)
+    }
+}
+
+impl PartialEq for VfsFiles {
+    fn eq(&self, other: &Self) -> bool {
+        self.files == other.files
+    }
+}
+
+impl From<Vec<VfsFile>> for VfsFiles {
+    fn from(files: Vec<VfsFile>) -> Self {
+        Self {
+            files,
+            indexes_by_hash: None,
+            decoded_files: HashMap::new(),
+        }
+    }
+}
+
+impl From<VfsFiles> for Vec<VfsFile> {
+    fn from(vfs_files: VfsFiles) -> Self {
+        vfs_files.files
+    }
+}
+
+fn content_hash(content: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A file as it is stored in the global array of files.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct VfsFile {
-    content: String,
+#[serde(untagged)]
+pub enum VfsFile {
+    /// A file stored with its whole content.
+    Full {
+        content: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        highlights: Vec<Vec<VfsToken>>,
+    },
+    /// A file stored as the changes to another file.
+    Delta { delta: VfsDelta },
+    Binary(VfsBinary),
 }
 
 impl VfsFile {
     pub fn new(content: String) -> Self {
-        Self { content }
+        Self::Full {
+            content,
+            highlights: Vec::new(),
+        }
+    }
+}
+
+/// A file that is not text, e.g. an image or a font.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VfsBinary {
+    pub mime: String,
+    #[serde(with = "base64_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl VfsBinary {
+    pub fn new(mime: String, data: Vec<u8>) -> Self {
+        Self { mime, data }
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.mime.starts_with("image/")
+    }
+
+    /// Returns a `data:` URL with the contents of the file.
+    pub fn data_url(&self) -> String {
+        format!("data:{};base64,{}", self.mime, base64::encode(&self.data))
+    }
+}
+
+mod base64_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A piece of a line that is highlighted with the same class. `len` is the
+/// length of the piece in bytes and `class` is the kind of token (e.g.
+/// `keyword` or `string`) or empty for plain text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "(usize, String)", into = "(usize, String)")]
+pub struct VfsToken {
+    pub len: usize,
+    pub class: String,
+}
+
+impl VfsToken {
+    pub fn new(len: usize, class: String) -> Self {
+        Self { len, class }
+    }
+}
+
+impl From<(usize, String)> for VfsToken {
+    fn from((len, class): (usize, String)) -> Self {
+        Self { len, class }
+    }
+}
+
+impl From<VfsToken> for (usize, String) {
+    fn from(token: VfsToken) -> Self {
+        (token.len, token.class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Vfs, VfsPath};
+
+    #[test]
+    fn test_add_file_deduplicates_content() {
+        let files = VfsFilesHandle::new();
+
+        let main_index = files.add_file(VfsFile::new("fn main() {}".into()));
+        let lib_index = files.add_file(VfsFile::new("pub mod vfs;".into()));
+        let bin_index = files.add_file(VfsFile::new("fn main() {}".into()));
+
+        assert_eq!(main_index, bin_index);
+        assert_ne!(main_index, lib_index);
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_add_distinct_file_keeps_duplicates() {
+        let files = VfsFilesHandle::new();
+
+        let main_index = files.add_file(VfsFile::new("fn main() {}".into()));
+        let copy_index = files.add_distinct_file(VfsFile::new("fn main() {}".into()));
+
+        assert_ne!(main_index, copy_index);
+        assert_eq!(files.read_file(copy_index).as_deref(), Some("fn main() {}"));
+        assert_eq!(files.len(), 2);
+    }
+
+    #[test]
+    fn test_write_file_reindexes_content() {
+        let files = VfsFilesHandle::new();
+
+        let file_index = files.add_file(VfsFile::new("fn main() {}".into()));
+        files.write_file(file_index, "pub mod vfs;".into());
+
+        assert_eq!(
+            files.add_file(VfsFile::new("pub mod vfs;".into())),
+            file_index
+        );
+        assert_ne!(
+            files.add_file(VfsFile::new("fn main() {}".into())),
+            file_index
+        );
+    }
+
+    #[test]
+    fn test_guide_stores_distinct_content_once() -> anyhow::Result<()> {
+        let mut vfs = Vfs::new();
+        let main_path = VfsPath::new("src/main.rs")?;
+        let license_paths = [VfsPath::new("LICENSE")?, VfsPath::new("docs/LICENSE")?];
+
+        // Every page rewrites all the files but only `main.rs` changes, going
+        // back and forth between a few versions
+        for page in 0..50 {
+            let snapshot = vfs.add_snapshot();
+
+            for license_path in &license_paths {
+                snapshot.write_file(license_path, "MIT License".into());
+            }
+            snapshot.write_file(
+                &main_path,
+                format!("fn main() {{ let page = {}; }}", page % 5),
+            );
+        }
+
+        assert_eq!(vfs.snapshots.len(), 50);
+        assert_eq!(vfs.files.len(), 6);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_indexes_files() -> anyhow::Result<()> {
+        let files: VfsFilesHandle = serde_json::from_str(r#"[{ "content": "fn main() {}" }]"#)?;
+
+        assert_eq!(files.add_file(VfsFile::new("fn main() {}".into())), 0);
+        assert_eq!(
+            serde_json::to_string(&files)?,
+            r#"[{"content":"fn main() {}"}]"#
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_delta_file() {
+        let files = VfsFilesHandle::new();
+
+        let base_index = files.add_file(VfsFile::new("a\nb\nc\nd\n".into()));
+        let file_index = files.add_file(VfsFile::new("a\nb\nc\nd\ne\n".into()));
+        let delta = files.delta(file_index, base_index).unwrap();
+        files.set_delta(file_index, delta);
+
+        assert!(matches!(files.file(file_index), Some(VfsFile::Delta { .. })));
+        assert_eq!(files.read_file(file_index), Some("a\nb\nc\nd\ne\n".into()));
+
+        // Deltas are decoded again after the base changes
+        files.write_file(base_index, "z\nb\nc\nd\n".into());
+        assert_eq!(files.read_file(file_index), Some("z\nb\nc\nd\ne\n".into()));
+    }
+
+    #[test]
+    fn test_binary_file() -> anyhow::Result<()> {
+        let files = VfsFilesHandle::new();
+        let png = VfsBinary::new("image/png".into(), vec![0x89, b'P', b'N', b'G', 0]);
+
+        let file_index = files.add_file(VfsFile::Binary(png.clone()));
+        assert_eq!(files.add_file(VfsFile::Binary(png.clone())), file_index);
+        assert_eq!(files.read_binary(file_index), Some(png.clone()));
+        assert_eq!(files.read_file(file_index), None);
+
+        let json = serde_json::to_string(&files)?;
+        assert_eq!(json, r#"[{"mime":"image/png","data":"iVBORwA="}]"#);
+
+        let files: VfsFilesHandle = serde_json::from_str(&json)?;
+        assert_eq!(files.read_binary(file_index), Some(png));
+
+        Ok(())
     }
 }
@@ -3,6 +3,7 @@ use std::fmt::Display;
 use std::path::{Component, Path, PathBuf};
 
 use anyhow::{ensure, Result};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct VfsPath(PathBuf);
@@ -67,6 +68,19 @@ impl Display for VfsPath {
     }
 }
 
+impl Serialize for VfsPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for VfsPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        Self::new(path).map_err(de::Error::custom)
+    }
+}
+
 impl TryFrom<PathBuf> for VfsPath {
     type Error = anyhow::Error;
 
@@ -3,15 +3,31 @@ use serde::{Deserialize, Serialize};
 use super::directory::VfsRoot;
 use super::path::VfsPath;
 use super::VfsFilesHandle;
-use crate::vfs::VfsFile;
-use crate::VfsWalker;
+use crate::vfs::{VfsBinary, VfsChange, VfsFile};
+use crate::{PageMetadata, VfsToken, VfsWalker};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VfsSnapshot {
     #[serde(skip)]
     pub files: VfsFilesHandle,
     pub root: VfsRoot,
+    /// Identifies the page across rebuilds, unlike its position in the guide.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// The commit the snapshot was built from. Drafts don't have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub title: String,
+    #[serde(default, skip_serializing_if = "PageMetadata::is_empty")]
+    pub metadata: PageMetadata,
     pub page: String,
+    /// The files that changed since the previous snapshot.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<VfsChange>,
+    /// Whether this snapshot was built from unsaved changes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
 }
 
 impl VfsSnapshot {
@@ -19,7 +35,13 @@ impl VfsSnapshot {
         Self {
             files,
             root: VfsRoot::new(),
+            id: String::new(),
+            commit: None,
+            title: String::new(),
+            metadata: PageMetadata::default(),
             page: String::new(),
+            changes: Vec::new(),
+            draft: false,
         }
     }
 
@@ -47,6 +69,12 @@ impl VfsSnapshot {
         self.root.create_file(path, file_index);
     }
 
+    /// Like [create_file](VfsSnapshot::create_file) but for a binary file.
+    pub fn create_binary_file(&mut self, path: &VfsPath, binary: VfsBinary) {
+        let file_index = self.files.add_file(VfsFile::Binary(binary));
+        self.root.create_file(path, file_index);
+    }
+
     /// Writes `content` into the file at the given `path` if it exists.
     pub fn write_file(&mut self, path: &VfsPath, content: String) {
         // for now it just forwards to `create_file` but later it will be used to record
@@ -54,12 +82,30 @@ impl VfsSnapshot {
         self.create_file(path, content);
     }
 
+    /// Like [write_file](VfsSnapshot::write_file) but for a binary file.
+    pub fn write_binary_file(&mut self, path: &VfsPath, binary: VfsBinary) {
+        self.create_binary_file(path, binary);
+    }
+
     /// Reads the file content at the given `path` and returns it.
     pub fn read_file(&self, path: &VfsPath) -> Option<String> {
         let file_index = self.root.find_file(path)?;
         self.files.read_file(file_index)
     }
 
+    /// Reads the binary file at the given `path` and returns it.
+    pub fn read_binary(&self, path: &VfsPath) -> Option<VfsBinary> {
+        let file_index = self.root.find_file(path)?;
+        self.files.read_binary(file_index)
+    }
+
+    /// Reads the syntax highlighting of the file at the given `path` and returns
+    /// it.
+    pub fn read_highlights(&self, path: &VfsPath) -> Option<Vec<Vec<VfsToken>>> {
+        let file_index = self.root.find_file(path)?;
+        self.files.read_highlights(file_index)
+    }
+
     /// See [VfsRoot::delete_file](VfsRoot::delete_file)
     pub fn delete_file(&mut self, path: &VfsPath) {
         self.root.delete_file(path);
@@ -74,6 +120,35 @@ impl VfsSnapshot {
         self.page = page;
     }
 
+    pub fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    pub fn set_commit(&mut self, commit: Option<String>) {
+        self.commit = commit;
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    pub fn set_metadata(&mut self, metadata: PageMetadata) {
+        self.metadata = metadata;
+    }
+
+    pub fn set_changes(&mut self, changes: Vec<VfsChange>) {
+        self.changes = changes;
+    }
+
+    /// Returns how the file at `path` changed since the previous snapshot.
+    pub fn change(&self, path: &VfsPath) -> Option<&VfsChange> {
+        self.changes.iter().find(|change| change.path == *path)
+    }
+
+    pub fn set_draft(&mut self, draft: bool) {
+        self.draft = draft;
+    }
+
     pub fn walk(&self) -> VfsWalker<'_> {
         VfsWalker::new(&self.root.0)
     }
@@ -82,6 +157,7 @@ impl VfsSnapshot {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::VfsChangeKind;
 
     #[test]
     fn test_create_file() -> anyhow::Result<()> {
@@ -126,10 +202,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_changes() -> anyhow::Result<()> {
+        let mut snapshot = VfsSnapshot::new(VfsFilesHandle::new());
+        snapshot.set_changes(vec![
+            VfsChange::new(VfsChangeKind::Added, VfsPath::new("src/main.rs")?),
+            VfsChange::renamed(VfsPath::new("old.rs")?, VfsPath::new("new.rs")?),
+        ]);
+
+        assert_eq!(
+            snapshot
+                .change(&VfsPath::new("new.rs")?)
+                .map(|change| change.kind),
+            Some(VfsChangeKind::Renamed)
+        );
+        assert_eq!(snapshot.change(&VfsPath::new("old.rs")?), None);
+
+        insta::assert_ron_snapshot!(snapshot.changes, @r###"
+        [
+          VfsChange(
+            kind: added,
+            path: "src/main.rs",
+          ),
+          VfsChange(
+            kind: renamed,
+            path: "new.rs",
+            old_path: Some("old.rs"),
+          ),
+        ]
+        "###);
+
+        Ok(())
+    }
 }
 
 impl PartialEq for VfsSnapshot {
     fn eq(&self, other: &Self) -> bool {
-        self.root == other.root && self.page == other.page
+        self.root == other.root
+            && self.title == other.title
+            && self.metadata == other.metadata
+            && self.page == other.page
     }
 }
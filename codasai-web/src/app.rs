@@ -1,17 +1,31 @@
+use std::ops::RangeInclusive;
+
 use codasai_types::{Guide, VfsDirectoryOrFile, VfsPath};
+use gloo::events::EventListener;
 use yew::services::keyboard::KeyListenerHandle;
 use yew::services::KeyboardService;
-use yew::{html, Component, ComponentLink, KeyboardEvent, Properties};
+use yew::{classes, html, Callback, Component, ComponentLink, KeyboardEvent, Properties};
 
-use crate::components::{Editor, FileExplorer, Page};
+use crate::components::{BinaryViewer, Editor, FileExplorer, Page, TableOfContents, TocPage};
 use crate::highlighted_chunk::HighlightedChunk;
+use crate::icons;
+use crate::route::Route;
+use crate::tabs::Tabs;
 
 pub enum AppMessage {
     PreviousPage,
     NextPage,
     KeyDown(KeyboardEvent),
     OpenFile(VfsPath),
+    CloseTab(VfsPath),
+    OpenInSplit(VfsPath),
+    CloseSplit,
     ChunkRels(Vec<HighlightedChunk>),
+    ReloadGuide(Guide),
+    OpenPage(usize),
+    ToggleToc,
+    RouteChanged(Route),
+    SelectLines(RangeInclusive<usize>),
 }
 
 #[derive(Clone, PartialEq, Properties)]
@@ -23,9 +37,12 @@ pub struct App {
     guide: Guide,
     page_number: usize,
     _keyboard_handle: KeyListenerHandle,
+    _hash_listener: EventListener,
     link: ComponentLink<Self>,
-    file_path: Option<VfsPath>,
+    tabs: Tabs,
+    selected_lines: Option<RangeInclusive<usize>>,
     chunk_rels: Vec<HighlightedChunk>,
+    toc_open: bool,
 }
 
 impl Component for App {
@@ -33,49 +50,101 @@ impl Component for App {
     type Properties = AppProps;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Self {
+        let route_changed = link.callback(AppMessage::RouteChanged);
+        let hash_listener = EventListener::new(&yew::utils::window(), "hashchange", move |_| {
+            if let Some(route) = Route::current() {
+                route_changed.emit(route);
+            }
+        });
+
+        let mut app = Self {
             guide: props.guide,
             page_number: 0,
             _keyboard_handle: KeyboardService::register_key_down(
                 &yew::utils::window(),
                 link.callback(|k| AppMessage::KeyDown(k)),
             ),
+            _hash_listener: hash_listener,
             link,
-            file_path: None,
+            tabs: Tabs::default(),
+            selected_lines: None,
             chunk_rels: Vec::new(),
+            toc_open: false,
+        };
+
+        if let Some(route) = Route::current() {
+            app.open_route(route);
         }
+
+        app
     }
 
     fn update(&mut self, msg: Self::Message) -> yew::ShouldRender {
         match msg {
             AppMessage::PreviousPage => {
-                self.page_number = self.page_number.saturating_sub(1);
+                self.open_page(self.page_number.saturating_sub(1));
             },
             AppMessage::NextPage => {
-                self.page_number = self.page_number.saturating_add(1);
+                self.open_page(self.page_number.saturating_add(1));
             },
             AppMessage::KeyDown(e) => {
                 match e.key().as_ref() {
                     "ArrowLeft" => {
-                        self.page_number = self.page_number.saturating_sub(1);
+                        self.open_page(self.page_number.saturating_sub(1));
                     },
                     "ArrowRight" => {
-                        self.page_number = self
-                            .page_number
-                            .saturating_add(1)
-                            .min(self.guide.vfs.snapshots.len() - 1);
+                        self.open_page(self.page_number.saturating_add(1));
                     },
                     _ => {},
                 }
             },
             AppMessage::OpenFile(path) => {
-                self.file_path = Some(path);
+                self.tabs.open(path);
+                self.selected_lines = None;
+            },
+            AppMessage::CloseTab(path) => {
+                if self.tabs.close(&path, self.default_file_path()) {
+                    self.selected_lines = None;
+                }
+            },
+            AppMessage::OpenInSplit(path) => {
+                self.tabs.open_in_split(path);
+            },
+            AppMessage::CloseSplit => {
+                self.tabs.close_split();
             },
             AppMessage::ChunkRels(chunks) => {
                 self.chunk_rels = chunks;
             },
+            AppMessage::ReloadGuide(guide) => {
+                let page_id = self.guide.vfs.snapshots[self.page_number].id.clone();
+                self.guide = guide;
+                // Follow the page if it moved, e.g. because a page was inserted before it
+                self.page_number = self
+                    .find_page(&page_id)
+                    .unwrap_or(self.page_number)
+                    .min(self.guide.vfs.snapshots.len().saturating_sub(1));
+            },
+            AppMessage::OpenPage(page_number) => {
+                self.open_page(page_number);
+            },
+            AppMessage::ToggleToc => {
+                self.toc_open = !self.toc_open;
+            },
+            AppMessage::RouteChanged(route) => {
+                if route == self.route() {
+                    return false;
+                }
+
+                self.open_route(route);
+            },
+            AppMessage::SelectLines(lines) => {
+                self.selected_lines = Some(lines);
+            },
         };
 
+        self.route().push();
+
         true
     }
 
@@ -88,12 +157,26 @@ impl Component for App {
         let open_file = self.link.callback(|path| AppMessage::OpenFile(path));
 
         let on_chunk_rels = self.link.callback(|chunks| AppMessage::ChunkRels(chunks));
+        let referenced_files = self
+            .chunk_rels
+            .iter()
+            .map(|chunk| chunk.file.clone())
+            .collect::<Vec<_>>();
+
+        let draft_class = if snapshot.draft { Some("draft") } else { None };
+        let app_class = classes!("app", draft_class);
 
         html! {
-            <div class="app">
-                <Page content=snapshot.page.clone() on_open_file=open_file.clone() on_chunk_rels=on_chunk_rels />
-                { self.view_editor() }
-                <FileExplorer snapshot=snapshot.clone() on_open_file=open_file />
+            <div class=app_class>
+                { self.view_draft_notice() }
+                { self.view_toc() }
+                <Page content=snapshot.page.clone() metadata=snapshot.metadata.clone() on_open_file=open_file.clone() on_chunk_rels=on_chunk_rels />
+                { self.view_editors() }
+                <FileExplorer
+                    snapshot=snapshot.clone()
+                    open_file=self.open_file_path()
+                    referenced_files=referenced_files
+                    on_open_file=open_file />
 
                 { self.view_navigation() }
             </div>
@@ -102,52 +185,247 @@ impl Component for App {
 }
 
 impl App {
-    fn view_editor(&self) -> yew::Html {
+    fn route(&self) -> Route {
         let snapshot = &self.guide.vfs.snapshots[self.page_number];
+        let page = if snapshot.id.is_empty() {
+            (self.page_number + 1).to_string()
+        } else {
+            snapshot.id.clone()
+        };
 
-        let maybe_file_path = self.file_path.clone().or_else(|| {
-            snapshot
-                .walk()
-                .find(|entry| matches!(entry.entry, VfsDirectoryOrFile::File(_)))
-                .map(|e| e.path)
+        Route {
+            page,
+            file_path: self.tabs.opened_file().cloned(),
+            lines: self.selected_lines.clone(),
+        }
+    }
+
+    fn open_route(&mut self, route: Route) {
+        if let Some(page_number) = self.find_page(&route.page) {
+            self.page_number = page_number;
+        }
+        match route.file_path {
+            Some(file_path) => self.tabs.open(file_path),
+            None => self.tabs.show_default(),
+        }
+        self.selected_lines = route.lines;
+    }
+
+    /// Returns the index of the snapshot with the given id or number, starting
+    /// at 1.
+    fn find_page(&self, page: &str) -> Option<usize> {
+        let snapshots = &self.guide.vfs.snapshots;
+
+        snapshots
+            .iter()
+            .position(|snapshot| !page.is_empty() && snapshot.id == page)
+            .or_else(|| {
+                let page_number = page.parse::<usize>().ok()?.checked_sub(1)?;
+                Some(page_number.min(snapshots.len().saturating_sub(1)))
+            })
+    }
+
+    /// Opens the page, clearing the selected lines if it is a different page.
+    fn open_page(&mut self, page_number: usize) {
+        let page_number = page_number.min(self.guide.vfs.snapshots.len() - 1);
+
+        if page_number != self.page_number {
+            self.page_number = page_number;
+            self.selected_lines = None;
+        }
+    }
+
+    /// Returns the file shown in the editor.
+    fn open_file_path(&self) -> Option<VfsPath> {
+        self.tabs.active_file(self.default_file_path())
+    }
+
+    /// Returns the first file of the page, which is shown if none was opened.
+    fn default_file_path(&self) -> Option<VfsPath> {
+        let snapshot = &self.guide.vfs.snapshots[self.page_number];
+
+        snapshot
+            .walk()
+            .find(|entry| matches!(entry.entry, VfsDirectoryOrFile::File(_)))
+            .map(|e| e.path)
+    }
+
+    fn view_editors(&self) -> yew::Html {
+        let tabs = self.tabs.visible(self.default_file_path());
+        if tabs.is_empty() {
+            return html! {};
+        }
+
+        let split_file = self.tabs.split_file().map(|path| {
+            html! { <div class="split">{ self.view_file(path, false) }</div> }
         });
 
-        if let Some(file_path) = maybe_file_path {
-            if let Some(file_content) = snapshot.read_file(&file_path) {
-                let old_file_content = self
-                    .guide
-                    .vfs
-                    .snapshots
-                    .get(self.page_number - 1)
-                    .and_then(|s| s.read_file(&file_path));
-
-                let highlighted_chunks = self
-                    .chunk_rels
-                    .iter()
-                    .filter(|c| c.file == file_path)
-                    .cloned()
-                    .collect::<Vec<_>>();
-
-                html! {
-                <Editor
-                    name=file_path.file_name().to_string()
-                    new_content=file_content
-                    old_content=old_file_content
-                    highlighted_chunks=highlighted_chunks /> }
+        html! {
+            <div class="editors">
+                <div class="tabs">
+                    { for tabs.iter().map(|path| self.view_tab(path)) }
+                </div>
+                <div class="panes">
+                    { for self.open_file_path().map(|path| self.view_file(&path, true)) }
+                    { for split_file }
+                </div>
+            </div>
+        }
+    }
+
+    fn view_tab(&self, path: &VfsPath) -> yew::Html {
+        let snapshot = &self.guide.vfs.snapshots[self.page_number];
+        let deleted = snapshot.read_file(path).is_none() && snapshot.read_binary(path).is_none();
+
+        let active_class = if self.open_file_path().as_ref() == Some(path) {
+            Some("active")
+        } else {
+            None
+        };
+        let split_class = if self.tabs.split_file() == Some(path) {
+            Some("split")
+        } else {
+            None
+        };
+        let deleted_class = if deleted { Some("deleted") } else { None };
+
+        let open_path = path.clone();
+        let open_file = self
+            .link
+            .callback(move |_| AppMessage::OpenFile(open_path.clone()));
+        let split_path = path.clone();
+        let toggle_split = if split_class.is_some() {
+            self.link.callback(|_| AppMessage::CloseSplit)
+        } else {
+            self.link
+                .callback(move |_| AppMessage::OpenInSplit(split_path.clone()))
+        };
+        let close_path = path.clone();
+        let close_tab = self
+            .link
+            .callback(move |_| AppMessage::CloseTab(close_path.clone()));
+        let split_title = if split_class.is_some() {
+            "Close the split view"
+        } else {
+            "Open to the side"
+        };
+
+        html! {
+            <div class=classes!("tab", active_class, split_class, deleted_class) title=path.to_string()>
+                <span class="name" onclick=open_file>{ path.file_name() }</span>
+                <button class="split-toggle" onclick=toggle_split title=split_title>{ icons::split() }</button>
+                <button class="close" onclick=close_tab title="Close">{ icons::close() }</button>
+            </div>
+        }
+    }
+
+    /// Shows the file in the code of the page. Only the lines of the primary
+    /// pane can be selected, as they are part of the URL.
+    fn view_file(&self, file_path: &VfsPath, primary: bool) -> yew::Html {
+        let snapshot = &self.guide.vfs.snapshots[self.page_number];
+        let previous_snapshot = self
+            .page_number
+            .checked_sub(1)
+            .and_then(|page_number| self.guide.vfs.snapshots.get(page_number));
+
+        if let Some(file_content) = snapshot.read_file(file_path) {
+            let old_file_content = previous_snapshot.and_then(|s| s.read_file(file_path));
+
+            let file_highlights = snapshot.read_highlights(file_path).unwrap_or_default();
+            let old_file_highlights = previous_snapshot
+                .and_then(|s| s.read_highlights(file_path))
+                .unwrap_or_default();
+
+            let (selected_lines, select_lines) = if primary {
+                (
+                    self.selected_lines.clone(),
+                    self.link.callback(AppMessage::SelectLines),
+                )
             } else {
-                html! {}
+                (None, Callback::noop())
+            };
+
+            let highlighted_chunks = self
+                .chunk_rels
+                .iter()
+                .filter(|c| &c.file == file_path)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            html! {
+            <Editor
+                name=file_path.file_name().to_string()
+                new_content=file_content
+                old_content=old_file_content
+                new_highlights=file_highlights
+                old_highlights=old_file_highlights
+                highlighted_chunks=highlighted_chunks
+                selected_lines=selected_lines
+                on_select_lines=select_lines /> }
+        } else if let Some(binary) = snapshot.read_binary(file_path) {
+            html! {
+            <BinaryViewer
+                name=file_path.file_name().to_string()
+                binary=binary /> }
+        } else {
+            html! {
+                <div class="editor">
+                    <div class="file-name">
+                        <span>{ file_path.file_name() }</span>
+                    </div>
+                    <div class="inner deleted">
+                        <p>{ "This file doesn't exist in this page." }</p>
+                    </div>
+                </div>
+            }
+        }
+    }
+
+    fn view_draft_notice(&self) -> yew::Html {
+        if self.guide.vfs.snapshots[self.page_number].draft {
+            html! {
+                <div class="draft-notice">{ "Draft: this page has not been saved yet" }</div>
             }
         } else {
             html! {}
         }
     }
 
+    fn view_toc(&self) -> yew::Html {
+        if !self.toc_open {
+            return html! {};
+        }
+
+        let pages = self
+            .guide
+            .vfs
+            .snapshots
+            .iter()
+            .map(|snapshot| TocPage {
+                title: snapshot.title.clone(),
+                reading_time: snapshot.metadata.reading_time,
+            })
+            .collect::<Vec<_>>();
+        let open_page = self.link.callback(AppMessage::OpenPage);
+
+        html! {
+            <TableOfContents
+                chapters=self.guide.chapters.clone()
+                pages=pages
+                page_number=self.page_number
+                on_open_page=open_page />
+        }
+    }
+
     fn view_navigation(&self) -> yew::Html {
         let previous_page = self.link.callback(|_| AppMessage::PreviousPage);
         let next_page = self.link.callback(|_| AppMessage::NextPage);
+        let toggle_toc = self.link.callback(|_| AppMessage::ToggleToc);
 
         html! {
             <div class="navigation">
+                <button class="toc-toggle" onclick=toggle_toc>{ "Contents" }</button>
+
                 { if self.page_number != 0 {
                     html! { <button onclick=previous_page>{ "Previous" }</button> }
                 } else {
@@ -1,7 +1,11 @@
+mod binary_viewer;
 mod editor;
 mod explorer;
 mod page;
+mod toc;
 
+pub use binary_viewer::BinaryViewer;
 pub use editor::Editor;
 pub use explorer::FileExplorer;
 pub use page::Page;
+pub use toc::{TableOfContents, TocPage};
@@ -0,0 +1,67 @@
+use codasai_types::VfsBinary;
+use yew::{html, Component, Properties};
+
+#[derive(Debug, Clone, PartialEq, Properties)]
+pub struct BinaryViewerProperties {
+    pub name: String,
+    pub binary: VfsBinary,
+}
+
+/// Shows a file that is not text. Images are shown inline while other files
+/// can only be downloaded.
+pub struct BinaryViewer {
+    props: BinaryViewerProperties,
+    data_url: String,
+}
+
+impl Component for BinaryViewer {
+    type Message = ();
+    type Properties = BinaryViewerProperties;
+
+    fn create(props: Self::Properties, _link: yew::ComponentLink<Self>) -> Self {
+        let data_url = props.binary.data_url();
+
+        Self { props, data_url }
+    }
+
+    fn update(&mut self, _msg: Self::Message) -> yew::ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> yew::ShouldRender {
+        if self.props != props {
+            self.data_url = props.binary.data_url();
+            self.props = props;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> yew::Html {
+        let content = if self.props.binary.is_image() {
+            html! {
+                <img src=self.data_url.clone() alt=self.props.name.clone() />
+            }
+        } else {
+            html! {
+                <>
+                    <p>{ format!("This file can't be shown because it is not text ({}).", self.props.binary.mime) }</p>
+                    <a href=self.data_url.clone() download=self.props.name.clone()>{ "Download" }</a>
+                </>
+            }
+        };
+
+        html! {
+            <div class="editor">
+                <div class="file-name">
+                    <span>{ &self.props.name }</span>
+                </div>
+                <div class="inner binary">
+                    { content }
+                </div>
+            </div>
+        }
+    }
+}
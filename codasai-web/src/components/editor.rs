@@ -1,25 +1,39 @@
-use dissimilar::Chunk;
-use yew::{classes, html, Component, Properties};
+use std::ops::RangeInclusive;
 
+use codasai_types::VfsToken;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, ScrollIntoViewOptions, ScrollLogicalPosition};
+use yew::{classes, html, Callback, Component, MouseEvent, Properties};
+
+use crate::diffed_line::{changed_bytes, diffed_lines, DiffedLine, Segment, SegmentKind};
 use crate::highlighted_chunk::HighlightedChunk;
+use crate::icons;
 
 #[derive(Debug, Clone, PartialEq, Properties)]
 pub struct EditorProperties {
     pub name: String,
     pub old_content: Option<String>,
     pub new_content: String,
+    pub old_highlights: Vec<Vec<VfsToken>>,
+    pub new_highlights: Vec<Vec<VfsToken>>,
     pub highlighted_chunks: Vec<HighlightedChunk>,
+    /// The lines that are highlighted and scrolled into view.
+    pub selected_lines: Option<RangeInclusive<usize>>,
+    pub on_select_lines: Callback<RangeInclusive<usize>>,
 }
 
 pub enum EditorMessage {
     ToggleView,
+    /// A line number was clicked, extending the selection if shift was held.
+    SelectLine(usize, bool),
 }
 
 pub struct Editor {
     props: EditorProperties,
-    old_content: String,
-    new_content: String,
+    old_lines: Vec<DiffedLine>,
+    new_lines: Vec<DiffedLine>,
     showing_old: bool,
+    scroll_to_selection: bool,
     link: yew::ComponentLink<Self>,
 }
 
@@ -28,12 +42,13 @@ impl Component for Editor {
     type Properties = EditorProperties;
 
     fn create(props: Self::Properties, link: yew::ComponentLink<Self>) -> Self {
-        let (old_content, new_content) = diffed_content_from_properties(&props);
+        let (old_lines, new_lines) = diffed_content_from_properties(&props);
 
         Self {
+            scroll_to_selection: props.selected_lines.is_some(),
             props,
-            old_content,
-            new_content,
+            old_lines,
+            new_lines,
             showing_old: false,
             link,
         }
@@ -46,6 +61,18 @@ impl Component for Editor {
                     self.showing_old = !self.showing_old;
                 }
             },
+            EditorMessage::SelectLine(number, extend) => {
+                let lines = match &self.props.selected_lines {
+                    Some(lines) if extend => {
+                        let start = *lines.start();
+                        start.min(number)..=start.max(number)
+                    },
+                    _ => number..=number,
+                };
+                self.props.on_select_lines.emit(lines);
+
+                return false;
+            },
         }
 
         true
@@ -53,11 +80,19 @@ impl Component for Editor {
 
     fn change(&mut self, props: Self::Properties) -> yew::ShouldRender {
         if self.props != props {
-            self.showing_old = false;
+            if self.props.selected_lines != props.selected_lines {
+                self.scroll_to_selection = props.selected_lines.is_some();
+            }
 
-            let (old_content, new_content) = diffed_content_from_properties(&props);
-            self.old_content = old_content;
-            self.new_content = new_content;
+            if self.props.old_content != props.old_content
+                || self.props.new_content != props.new_content
+            {
+                self.showing_old = false;
+            }
+
+            let (old_lines, new_lines) = diffed_content_from_properties(&props);
+            self.old_lines = old_lines;
+            self.new_lines = new_lines;
 
             self.props = props;
 
@@ -70,10 +105,10 @@ impl Component for Editor {
     fn view(&self) -> yew::Html {
         let toggle_view = self.link.callback(|_| EditorMessage::ToggleView);
 
-        let (content, class) = if self.showing_old {
-            (&self.old_content, "showing-old")
+        let (lines, class) = if self.showing_old {
+            (&self.old_lines, "showing-old")
         } else {
-            (&self.new_content, "showing-new")
+            (&self.new_lines, "showing-new")
         };
 
         let icon_hidden = self.props.old_content.is_none();
@@ -82,24 +117,38 @@ impl Component for Editor {
             <div class="editor">
                 <div class=classes!("file-name", class) onclick=toggle_view>
                     <span>{ &self.props.name }</span>
-                    <i hidden=icon_hidden class="diff-icon diff-new-icon fas fa-plus-square"></i>
-                    <i hidden=icon_hidden class="diff-icon diff-old-icon fas fa-minus-square"></i>
+                    <i hidden=icon_hidden class="diff-icon diff-new-icon">{ icons::plus_square() }</i>
+                    <i hidden=icon_hidden class="diff-icon diff-old-icon">{ icons::minus_square() }</i>
                 </div>
                 <div class="inner">
-                    { for content.split('\n').enumerate().map(|(n, l)| self.view_line(l, n+1)) }
+                    { for lines.iter().enumerate().map(|(n, l)| self.view_line(l, n+1)) }
                 </div>
             </div>
         }
     }
+
+    fn rendered(&mut self, _first_render: bool) {
+        if !self.scroll_to_selection {
+            return;
+        }
+        self.scroll_to_selection = false;
+
+        let selected_line = yew::utils::document()
+            .query_selector(".editor .line.selected")
+            .ok()
+            .flatten()
+            .and_then(|line| line.dyn_into::<Element>().ok());
+
+        if let Some(line) = selected_line {
+            let mut options = ScrollIntoViewOptions::new();
+            options.block(ScrollLogicalPosition::Center);
+            line.scroll_into_view_with_scroll_into_view_options(&options);
+        }
+    }
 }
 
 impl Editor {
-    fn view_line(&self, line: &str, number: usize) -> yew::Html {
-        let div = yew::utils::document().create_element("code").unwrap();
-        div.set_class_name("content");
-        div.set_inner_html(line);
-
-        let line = yew::Html::VRef(div.into());
+    fn view_line(&self, line: &DiffedLine, number: usize) -> yew::Html {
         let mut highlight_class = None;
 
         for chunk in &self.props.highlighted_chunks {
@@ -108,49 +157,116 @@ impl Editor {
             }
         }
 
+        let selected_class = match &self.props.selected_lines {
+            Some(lines) if lines.contains(&number) => Some("selected"),
+            _ => None,
+        };
+        let select_line = self
+            .link
+            .callback(move |e: MouseEvent| EditorMessage::SelectLine(number, e.shift_key()));
+
         html! {
-            <div class="line">
-                <div class=classes!("number", highlight_class)>
+            <div class=classes!("line", selected_class)>
+                <div class=classes!("number", highlight_class) onclick=select_line>
                     { number }
                 </div>
-                { line }
+                <code class="content">
+                    { for line.segments.iter().map(view_segment) }
+                </code>
             </div>
         }
     }
 }
 
-fn diffed_content_from_properties(props: &EditorProperties) -> (String, String) {
-    let mut old_diffed_content = String::new();
-    let mut new_diffed_content = String::new();
+fn view_segment(segment: &Segment) -> yew::Html {
+    let highlight_class = if segment.class.is_empty() {
+        None
+    } else {
+        Some(format!("hl-{}", segment.class))
+    };
 
-    if let Some(old_content) = props.old_content.as_ref() {
-        for chunk in dissimilar::diff(&old_content, &props.new_content) {
-            match chunk {
-                Chunk::Equal(c) => {
-                    old_diffed_content.push_str(c);
-                    new_diffed_content.push_str(c);
-                },
-                Chunk::Insert(c) => {
-                    let lines = c
-                        .split('\n')
-                        .map(|l| format!("<span class=\"inserted\">{}</span>", l))
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    new_diffed_content.push_str(&lines);
-                },
-                Chunk::Delete(c) => {
-                    let lines = c
-                        .split('\n')
-                        .map(|l| format!("<span class=\"deleted\">{}</span>", l))
-                        .collect::<Vec<_>>()
-                        .join("\n");
-                    old_diffed_content.push_str(&lines);
-                },
-            }
+    let kind_class = match segment.kind {
+        SegmentKind::Unchanged => None,
+        SegmentKind::Inserted => Some("inserted"),
+        SegmentKind::Deleted => Some("deleted"),
+    };
+
+    if highlight_class.is_none() && kind_class.is_none() {
+        html! { &segment.text }
+    } else {
+        html! {
+            <span class=classes!(highlight_class, kind_class)>{ &segment.text }</span>
         }
+    }
+}
+
+fn diffed_content_from_properties(props: &EditorProperties) -> (Vec<DiffedLine>, Vec<DiffedLine>) {
+    if let Some(old_content) = props.old_content.as_ref() {
+        let (old_changes, new_changes) = changed_bytes(old_content, &props.new_content);
+
+        let old_lines = diffed_lines(
+            old_content,
+            &props.old_highlights,
+            &old_changes,
+            SegmentKind::Deleted,
+        );
+        let new_lines = diffed_lines(
+            &props.new_content,
+            &props.new_highlights,
+            &new_changes,
+            SegmentKind::Inserted,
+        );
+
+        (old_lines, new_lines)
     } else {
-        new_diffed_content = props.new_content.clone();
+        let new_lines = diffed_lines(
+            &props.new_content,
+            &props.new_highlights,
+            &[],
+            SegmentKind::Inserted,
+        );
+
+        (Vec::new(), new_lines)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use yew::virtual_dom::VNode;
+
+    use super::*;
+
+    fn text_of(node: &VNode) -> Option<&str> {
+        match node {
+            VNode::VText(text) => Some(&text.text),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_segments_are_rendered_as_text() {
+        let plain = view_segment(&Segment {
+            text: "<script>alert(1)</script>".to_string(),
+            class: String::new(),
+            kind: SegmentKind::Unchanged,
+        });
+        assert_eq!(text_of(&plain), Some("<script>alert(1)</script>"));
 
-    (old_diffed_content, new_diffed_content)
+        let deleted = view_segment(&Segment {
+            text: "<img src=x onerror=alert(1)>".to_string(),
+            class: "string".to_string(),
+            kind: SegmentKind::Deleted,
+        });
+        match deleted {
+            VNode::VTag(tag) => {
+                assert_eq!(tag.tag(), "span");
+                assert_eq!(tag.children.len(), 1);
+                assert_eq!(
+                    text_of(&tag.children[0]),
+                    Some("<img src=x onerror=alert(1)>")
+                );
+            },
+            node => panic!("expected a span, found {:?}", node),
+        }
+    }
 }
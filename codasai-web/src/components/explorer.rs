@@ -1,20 +1,36 @@
-use codasai_types::{VfsDirectoryOrFile, VfsPath, VfsSnapshot, VfsWalkerEntry};
+use std::collections::{HashMap, HashSet};
+
+use codasai_types::{
+    VfsChange, VfsChangeKind, VfsDirectoryOrFile, VfsPath, VfsSnapshot, VfsWalkerEntry,
+};
 use yew::{html, Callback, Component, ComponentLink, Properties};
 
+use crate::icons;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExplorerMessage {
     OpenFile(VfsPath),
+    ToggleDirectory(VfsPath),
 }
 
 #[derive(Debug, Clone, PartialEq, Properties)]
 pub struct FileExplorerProperties {
     pub snapshot: VfsSnapshot,
+    /// The file shown in the editor.
+    #[prop_or_default]
+    pub open_file: Option<VfsPath>,
+    /// The files referenced by the page.
+    #[prop_or_default]
+    pub referenced_files: Vec<VfsPath>,
     pub on_open_file: Callback<VfsPath>,
 }
 
 pub struct FileExplorer {
     props: FileExplorerProperties,
     link: ComponentLink<Self>,
+    /// Whether the directories opened or closed by the user are expanded. It's
+    /// kept across pages, unlike the directories expanded automatically.
+    toggled_directories: HashMap<VfsPath, bool>,
 }
 
 impl Component for FileExplorer {
@@ -22,7 +38,11 @@ impl Component for FileExplorer {
     type Properties = FileExplorerProperties;
 
     fn create(props: Self::Properties, link: yew::ComponentLink<Self>) -> Self {
-        Self { props, link }
+        Self {
+            props,
+            link,
+            toggled_directories: HashMap::new(),
+        }
     }
 
     fn update(&mut self, msg: Self::Message) -> yew::ShouldRender {
@@ -30,6 +50,10 @@ impl Component for FileExplorer {
             ExplorerMessage::OpenFile(path) => {
                 self.props.on_open_file.emit(path);
             },
+            ExplorerMessage::ToggleDirectory(path) => {
+                let expanded = self.is_expanded(&path, &self.auto_expanded_directories());
+                self.toggled_directories.insert(path, !expanded);
+            },
         }
 
         true
@@ -41,13 +65,32 @@ impl Component for FileExplorer {
     }
 
     fn view(&self) -> yew::Html {
-        let walker = self.props.snapshot.walk();
+        let auto_expanded = self.auto_expanded_directories();
+
+        // The walker lists the contents of a directory right after it, so they
+        // are skipped until an entry at the level of the collapsed directory
+        let mut collapsed_level = None;
+        let entries = self.props.snapshot.walk().filter(|entry| {
+            match collapsed_level {
+                Some(level) if entry.level > level => return false,
+                _ => collapsed_level = None,
+            }
+
+            if let VfsDirectoryOrFile::Directory(_) = entry.entry {
+                if !self.is_expanded(&entry.path, &auto_expanded) {
+                    collapsed_level = Some(entry.level);
+                }
+            }
+            true
+        });
+
         html! {
             <>
             <div class="file-explorer">
+                { self.view_changes() }
                 <h3>{ "Explorer" }</h3>
-                {for walker.map(|entry| {
-                    self.view_entry(&entry)
+                {for entries.map(|entry| {
+                    self.view_entry(&entry, &auto_expanded)
                 })}
             </div>
             </>
@@ -56,29 +99,136 @@ impl Component for FileExplorer {
 }
 
 impl FileExplorer {
-    fn view_entry(&self, entry: &VfsWalkerEntry) -> yew::Html {
-        let (entry_class, name, data_file) = match entry.entry {
-            VfsDirectoryOrFile::Directory(name) => ("directory", name, None),
-            VfsDirectoryOrFile::File(name) => ("file", name, Some(entry.path.clone())),
+    fn view_entry(&self, entry: &VfsWalkerEntry, auto_expanded: &HashSet<VfsPath>) -> yew::Html {
+        let path = entry.path.clone();
+        let (entry_class, name, message, icons) = match entry.entry {
+            VfsDirectoryOrFile::Directory(name) => {
+                let icons = if self.is_expanded(&entry.path, auto_expanded) {
+                    html! { <>{ icons::chevron_down() }{ icons::folder_open() }</> }
+                } else {
+                    html! { <>{ icons::chevron_right() }{ icons::folder() }</> }
+                };
+                ("directory", name, ExplorerMessage::ToggleDirectory(path), icons)
+            },
+            VfsDirectoryOrFile::File(name) => {
+                ("file", name, ExplorerMessage::OpenFile(path), icons::file_type(name))
+            },
+        };
+
+        let badge = match entry.entry {
+            VfsDirectoryOrFile::File(_) => self.props.snapshot.change(&entry.path).map(view_badge),
+            VfsDirectoryOrFile::Directory(_) => None,
+        };
+        let open_class = if self.props.open_file.as_ref() == Some(&entry.path) {
+            Some("open")
+        } else {
+            None
         };
 
-        let entry_class = yew::classes!(entry_class, "entry");
+        let entry_class = yew::classes!(entry_class, "entry", open_class);
         let style = format!(
             "padding-left: calc({} * var(--entry-level-padding))",
             entry.level
         );
 
-        let on_open_file = self
-            .link
-            .batch_callback(move |_| data_file.clone().map(ExplorerMessage::OpenFile));
+        let on_click = self.link.callback(move |_| message.clone());
 
         html! {
-            <div class=entry_class onclick=on_open_file>
+            <div class=entry_class onclick=on_click title=entry.path.to_string()>
                 <div class="label" style=style>
-                <i class="icon fas fa-angry"></i>
+                { icons }
                 <span>{ name }</span>
+                { for badge }
                 </div>
                 </div>
         }
     }
+
+    fn is_expanded(&self, path: &VfsPath, auto_expanded: &HashSet<VfsPath>) -> bool {
+        self.toggled_directories
+            .get(path)
+            .copied()
+            .unwrap_or_else(|| auto_expanded.contains(path))
+    }
+
+    /// Returns the directories with files that changed in this page, that are
+    /// referenced by it or that are open, which are expanded unless the user
+    /// closed them.
+    fn auto_expanded_directories(&self) -> HashSet<VfsPath> {
+        let changed_files = self
+            .props
+            .snapshot
+            .changes
+            .iter()
+            .filter(|change| change.kind != VfsChangeKind::Deleted)
+            .map(|change| &change.path);
+        let files = changed_files
+            .chain(&self.props.referenced_files)
+            .chain(&self.props.open_file);
+
+        let mut directories = HashSet::new();
+        for file in files {
+            let components = file.components().collect::<Vec<_>>();
+            for end in 1..components.len() {
+                if let Ok(directory) = VfsPath::new(components[..end].join("/")) {
+                    directories.insert(directory);
+                }
+            }
+        }
+
+        directories
+    }
+
+    /// Lists the files that changed in this page. Deleted files can't be opened
+    /// so they are only shown struck through.
+    fn view_changes(&self) -> yew::Html {
+        let changes = &self.props.snapshot.changes;
+        if changes.is_empty() {
+            return html! {};
+        }
+
+        html! {
+            <div class="changes">
+                <h3>{ "Files changed in this step" }</h3>
+                { for changes.iter().map(|change| self.view_change(change)) }
+            </div>
+        }
+    }
+
+    fn view_change(&self, change: &VfsChange) -> yew::Html {
+        let deleted = change.kind == VfsChangeKind::Deleted;
+        let path = change.path.clone();
+        let on_open_file = self
+            .link
+            .batch_callback(move |_| (!deleted).then(|| ExplorerMessage::OpenFile(path.clone())));
+
+        let label = match &change.old_path {
+            Some(old_path) => format!("{} → {}", old_path, change.path),
+            None => change.path.to_string(),
+        };
+        let deleted_class = if deleted { Some("deleted") } else { None };
+
+        html! {
+            <div class=yew::classes!("entry", "change", deleted_class) onclick=on_open_file title=label.clone()>
+                <div class="label">
+                { view_badge(change) }
+                <span class="path">{ label }</span>
+                </div>
+            </div>
+        }
+    }
+}
+
+/// Shows the kind of change as a letter, like git does.
+fn view_badge(change: &VfsChange) -> yew::Html {
+    let (letter, kind_class) = match change.kind {
+        VfsChangeKind::Added => ("A", "added"),
+        VfsChangeKind::Modified => ("M", "modified"),
+        VfsChangeKind::Deleted => ("D", "deleted"),
+        VfsChangeKind::Renamed => ("R", "renamed"),
+    };
+
+    html! {
+        <span class=yew::classes!("change-badge", kind_class)>{ letter }</span>
+    }
 }
@@ -1,8 +1,8 @@
-use codasai_types::VfsPath;
+use codasai_types::{PageMetadata, VfsPath};
 use gloo::events::EventListener;
 use wasm_bindgen::JsCast;
 use web_sys::{Element, EventTarget};
-use yew::{Callback, Component, ComponentLink, Html, Properties};
+use yew::{html, Callback, Component, ComponentLink, Html, Properties};
 
 use crate::highlighted_chunk::HighlightedChunk;
 
@@ -14,6 +14,7 @@ pub enum PageMessage {
 #[derive(Debug, Clone, Properties, PartialEq)]
 pub struct PageProperties {
     pub content: String,
+    pub metadata: PageMetadata,
     pub on_open_file: Callback<VfsPath>,
     pub on_chunk_rels: Callback<Vec<HighlightedChunk>>,
 }
@@ -45,7 +46,9 @@ impl Component for Page {
     }
 
     fn change(&mut self, props: Self::Properties) -> yew::ShouldRender {
-        let should_render = if self.props.content != props.content {
+        let should_render = if self.props.content != props.content
+            || self.props.metadata != props.metadata
+        {
             self.anchor_listeners.clear();
             true
         } else {
@@ -59,10 +62,15 @@ impl Component for Page {
 
     fn view(&self) -> yew::Html {
         let div = yew::utils::document().create_element("div").unwrap();
-        div.set_class_name("markdown-body page");
+        div.set_class_name("page-content");
         div.set_inner_html(&self.props.content);
 
-        Html::VRef(div.into())
+        html! {
+            <div class="markdown-body page">
+                { self.view_metadata() }
+                { Html::VRef(div.into()) }
+            </div>
+        }
     }
 
     fn rendered(&mut self, _first_render: bool) {
@@ -76,6 +84,44 @@ impl Component for Page {
 }
 
 impl Page {
+    fn view_metadata(&self) -> yew::Html {
+        let metadata = &self.props.metadata;
+        if metadata.is_empty() {
+            return html! {};
+        }
+
+        let authors = if metadata.authors.is_empty() {
+            html! {}
+        } else {
+            html! { <span class="authors">{ format!("By {}", metadata.authors.join(", ")) }</span> }
+        };
+
+        let reading_time = match metadata.reading_time {
+            Some(reading_time) => {
+                html! { <span class="reading-time">{ format!("{} min read", reading_time) }</span> }
+            },
+            None => html! {},
+        };
+
+        let summary = match &metadata.summary {
+            Some(summary) => html! { <p class="summary">{ summary }</p> },
+            None => html! {},
+        };
+
+        html! {
+            <div class="page-metadata">
+                <div class="byline">
+                    { authors }
+                    { reading_time }
+                </div>
+                { summary }
+                <ul class="tags">
+                    { for metadata.tags.iter().map(|tag| html! { <li class="tag">{ tag }</li> }) }
+                </ul>
+            </div>
+        }
+    }
+
     fn set_file_links(&mut self) {
         let anchors = yew::utils::document()
             .query_selector_all(".page button[data-file]")
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use codasai_types::GuideChapter;
+use yew::{classes, html, Callback, Component, ComponentLink, Properties};
+
+use crate::icons;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TocMessage {
+    ToggleChapter(usize),
+    OpenPage(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocPage {
+    pub title: String,
+    /// Estimated time to read the page in minutes, if known.
+    pub reading_time: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Properties)]
+pub struct TocProperties {
+    pub chapters: Vec<GuideChapter>,
+    pub pages: Vec<TocPage>,
+    pub page_number: usize,
+    pub on_open_page: Callback<usize>,
+}
+
+/// A sidebar that lists the pages of the guide grouped by chapter. Chapters
+/// can be collapsed by clicking on their title.
+pub struct TableOfContents {
+    props: TocProperties,
+    link: ComponentLink<Self>,
+    collapsed_chapters: HashSet<usize>,
+}
+
+impl Component for TableOfContents {
+    type Message = TocMessage;
+    type Properties = TocProperties;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Self {
+            props,
+            link,
+            collapsed_chapters: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> yew::ShouldRender {
+        match msg {
+            TocMessage::ToggleChapter(chapter_index) => {
+                if !self.collapsed_chapters.remove(&chapter_index) {
+                    self.collapsed_chapters.insert(chapter_index);
+                }
+
+                true
+            },
+            TocMessage::OpenPage(page_number) => {
+                self.props.on_open_page.emit(page_number);
+                false
+            },
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> yew::ShouldRender {
+        if self.props != props {
+            if self.props.chapters != props.chapters {
+                self.collapsed_chapters.clear();
+            }
+            self.props = props;
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> yew::Html {
+        let content = if self.props.chapters.is_empty() {
+            self.view_pages(0..self.props.pages.len())
+        } else {
+            html! {
+                { for self.props.chapters.iter().enumerate().map(|(i, c)| self.view_chapter(i, c)) }
+            }
+        };
+
+        html! {
+            <div class="toc">
+                <h3>{ "Contents" }</h3>
+                { content }
+            </div>
+        }
+    }
+}
+
+impl TableOfContents {
+    fn view_chapter(&self, chapter_index: usize, chapter: &GuideChapter) -> yew::Html {
+        let pages = self.view_pages(chapter.pages.iter().copied());
+
+        // Pages before the first chapter don't have a heading
+        if chapter.title.is_empty() {
+            return pages;
+        }
+
+        let collapsed = self.collapsed_chapters.contains(&chapter_index);
+        let collapsed_class = if collapsed { Some("collapsed") } else { None };
+        let toggle_chapter = self
+            .link
+            .callback(move |_| TocMessage::ToggleChapter(chapter_index));
+        let icon = if collapsed {
+            icons::plus_square()
+        } else {
+            icons::minus_square()
+        };
+
+        html! {
+            <div class=classes!("chapter", collapsed_class)>
+                <div class="chapter-title" onclick=toggle_chapter>
+                    { icon }
+                    <span>{ &chapter.title }</span>
+                </div>
+                { if collapsed { html! {} } else { pages } }
+            </div>
+        }
+    }
+
+    fn view_pages(&self, pages: impl Iterator<Item = usize>) -> yew::Html {
+        html! {
+            <ol class="pages">
+                { for pages.map(|page_number| self.view_page(page_number)) }
+            </ol>
+        }
+    }
+
+    fn view_page(&self, page_number: usize) -> yew::Html {
+        let page = match self.props.pages.get(page_number) {
+            Some(page) => page,
+            None => return html! {},
+        };
+        let reading_time = match page.reading_time {
+            Some(reading_time) => {
+                html! { <span class="reading-time">{ format!("{} min", reading_time) }</span> }
+            },
+            None => html! {},
+        };
+        let current_class = if page_number == self.props.page_number {
+            Some("current")
+        } else {
+            None
+        };
+        let open_page = self
+            .link
+            .callback(move |_| TocMessage::OpenPage(page_number));
+
+        html! {
+            <li class=classes!("page-entry", current_class) onclick=open_page>
+                <span>{ &page.title }</span>
+                { reading_time }
+            </li>
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use codasai_types::VfsToken;
+use dissimilar::Chunk;
+
+/// A line of a file split in segments that share the same highlight class
+/// and change kind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffedLine {
+    pub segments: Vec<Segment>,
+}
+
+impl DiffedLine {
+    /// Splits `line` in segments given the highlight tokens of the line and
+    /// which of its bytes changed. Changed bytes are given `change_kind`.
+    pub fn new(
+        line: &str, tokens: &[VfsToken], changes: &[bool], change_kind: SegmentKind,
+    ) -> Self {
+        let mut classes = Vec::with_capacity(line.len());
+        for token in tokens {
+            classes.resize(classes.len() + token.len, token.class.as_str());
+        }
+        classes.resize(line.len(), "");
+
+        let changed = |i: usize| changes.get(i).copied().unwrap_or(false);
+
+        let mut segments = Vec::new();
+        let mut segment_start = 0;
+
+        for i in 1..=line.len() {
+            if i == line.len()
+                || (classes[i], changed(i)) != (classes[segment_start], changed(segment_start))
+            {
+                let kind = if changed(segment_start) {
+                    change_kind
+                } else {
+                    SegmentKind::Unchanged
+                };
+
+                segments.push(Segment {
+                    text: line[segment_start..i].to_string(),
+                    class: classes[segment_start].to_string(),
+                    kind,
+                });
+
+                segment_start = i;
+            }
+        }
+
+        Self { segments }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Segment {
+    pub text: String,
+    /// Highlight class of the segment or empty for plain text.
+    pub class: String,
+    pub kind: SegmentKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SegmentKind {
+    Unchanged,
+    Inserted,
+    Deleted,
+}
+
+/// Returns which bytes of `old` were deleted and which bytes of `new` were
+/// inserted.
+pub fn changed_bytes(old: &str, new: &str) -> (Vec<bool>, Vec<bool>) {
+    let mut old_changes = Vec::with_capacity(old.len());
+    let mut new_changes = Vec::with_capacity(new.len());
+
+    for chunk in dissimilar::diff(old, new) {
+        match chunk {
+            Chunk::Equal(c) => {
+                old_changes.resize(old_changes.len() + c.len(), false);
+                new_changes.resize(new_changes.len() + c.len(), false);
+            },
+            Chunk::Insert(c) => new_changes.resize(new_changes.len() + c.len(), true),
+            Chunk::Delete(c) => old_changes.resize(old_changes.len() + c.len(), true),
+        }
+    }
+
+    (old_changes, new_changes)
+}
+
+/// Splits `content` in lines of segments, given the highlight tokens of each
+/// line and which bytes of `content` changed.
+pub fn diffed_lines(
+    content: &str, highlights: &[Vec<VfsToken>], changes: &[bool], change_kind: SegmentKind,
+) -> Vec<DiffedLine> {
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+
+    for (line_index, line) in content.split('\n').enumerate() {
+        let tokens = highlights
+            .get(line_index)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let line_changes = changes.get(line_start..).unwrap_or_default();
+
+        lines.push(DiffedLine::new(line, tokens, line_changes, change_kind));
+        line_start += line.len() + 1;
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(text: &str, class: &str, kind: SegmentKind) -> Segment {
+        Segment {
+            text: text.to_string(),
+            class: class.to_string(),
+            kind,
+        }
+    }
+
+    fn token(len: usize, class: &str) -> VfsToken {
+        VfsToken::new(len, class.to_string())
+    }
+
+    #[test]
+    fn test_inserted_script_is_text() {
+        let old = "let x = \"\";";
+        let new = "let x = \"<script>alert(1)</script>\";";
+        let highlights = vec![vec![token(3, "storage"), token(5, ""), token(27, "string")]];
+
+        let (_, new_changes) = changed_bytes(old, new);
+        let lines = diffed_lines(new, &highlights, &new_changes, SegmentKind::Inserted);
+
+        assert_eq!(
+            lines,
+            vec![DiffedLine {
+                segments: vec![
+                    segment("let", "storage", SegmentKind::Unchanged),
+                    segment(" x = ", "", SegmentKind::Unchanged),
+                    segment("\"", "string", SegmentKind::Unchanged),
+                    segment("<script>alert(1)</script>", "string", SegmentKind::Inserted),
+                    segment("\"", "string", SegmentKind::Unchanged),
+                    segment(";", "", SegmentKind::Unchanged),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_deleted_img_is_text() {
+        let old = "a <img src=x onerror=alert(1)> b";
+        let new = "a b";
+
+        let (old_changes, _) = changed_bytes(old, new);
+        let lines = diffed_lines(old, &[], &old_changes, SegmentKind::Deleted);
+
+        assert_eq!(
+            lines,
+            vec![DiffedLine {
+                segments: vec![
+                    segment("a", "", SegmentKind::Unchanged),
+                    segment(" <img src=x onerror=alert(1)>", "", SegmentKind::Deleted),
+                    segment(" b", "", SegmentKind::Unchanged),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_segments_split_at_tokens_and_changes() {
+        let line = "let x = 1;";
+        let tokens = [
+            token(3, "storage"),
+            token(3, ""),
+            token(1, "keyword"),
+            token(1, ""),
+            token(1, "constant"),
+        ];
+        let changes = (0..line.len())
+            .map(|i| (2..9).contains(&i))
+            .collect::<Vec<_>>();
+
+        let line = DiffedLine::new(line, &tokens, &changes, SegmentKind::Inserted);
+
+        assert_eq!(
+            line.segments,
+            vec![
+                segment("le", "storage", SegmentKind::Unchanged),
+                segment("t", "storage", SegmentKind::Inserted),
+                segment(" x ", "", SegmentKind::Inserted),
+                segment("=", "keyword", SegmentKind::Inserted),
+                segment(" ", "", SegmentKind::Inserted),
+                segment("1", "constant", SegmentKind::Inserted),
+                segment(";", "", SegmentKind::Unchanged),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lines_take_their_own_changes() {
+        let (_, new_changes) = changed_bytes("a\nb\n", "a\nbc\n");
+        let lines = diffed_lines("a\nbc\n", &[], &new_changes, SegmentKind::Inserted);
+
+        assert_eq!(
+            lines,
+            vec![
+                DiffedLine {
+                    segments: vec![segment("a", "", SegmentKind::Unchanged)],
+                },
+                DiffedLine {
+                    segments: vec![
+                        segment("b", "", SegmentKind::Unchanged),
+                        segment("c", "", SegmentKind::Inserted),
+                    ],
+                },
+                DiffedLine::default(),
+            ]
+        );
+    }
+}
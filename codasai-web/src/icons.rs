@@ -0,0 +1,126 @@
+//! Icons used across the app. They are bundled instead of loaded from an icon
+//! font so exported guides can be read offline.
+
+use yew::{html, Html};
+
+pub fn plus_square() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="currentColor">
+            <path fill-rule="evenodd" d="M2 1h12a1 1 0 0 1 1 1v12a1 1 0 0 1-1 1H2a1 1 0 0 1-1-1V2a1 1 0 0 1 1-1zm5 3v3H4v2h3v3h2V9h3V7H9V4H7z" />
+        </svg>
+    }
+}
+
+pub fn minus_square() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="currentColor">
+            <path fill-rule="evenodd" d="M2 1h12a1 1 0 0 1 1 1v12a1 1 0 0 1-1 1H2a1 1 0 0 1-1-1V2a1 1 0 0 1 1-1zm2 6v2h8V7H4z" />
+        </svg>
+    }
+}
+
+pub fn file() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M3.5 1.5h5l4 4v9h-9z M8.5 1.5v4h4" />
+        </svg>
+    }
+}
+
+pub fn folder() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M1.5 3.5h5l1.5 1.5h6.5v8.5h-13z" />
+        </svg>
+    }
+}
+
+pub fn folder_open() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M1.5 13.5v-10h5l1.5 1.5h5.5v2 M1.5 13.5l2-6h11l-2 6z" />
+        </svg>
+    }
+}
+
+pub fn chevron_right() -> Html {
+    html! {
+        <svg class="icon chevron" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M6 3.5l4.5 4.5-4.5 4.5" />
+        </svg>
+    }
+}
+
+pub fn chevron_down() -> Html {
+    html! {
+        <svg class="icon chevron" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M3.5 6l4.5 4.5 4.5-4.5" />
+        </svg>
+    }
+}
+
+pub fn file_code() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M3.5 1.5h5l4 4v9h-9z M8.5 1.5v4h4 M7 8l-1.5 2 1.5 2 M9 8l1.5 2-1.5 2" />
+        </svg>
+    }
+}
+
+pub fn file_text() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M3.5 1.5h5l4 4v9h-9z M8.5 1.5v4h4 M5.5 8.5h5 M5.5 10.5h5 M5.5 12.5h3" />
+        </svg>
+    }
+}
+
+pub fn file_image() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M3.5 1.5h5l4 4v9h-9z M8.5 1.5v4h4 M3.5 13l3-3.5 2 2 1.5-1.5 2.5 3" />
+        </svg>
+    }
+}
+
+pub fn file_settings() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M3.5 1.5h5l4 4v9h-9z M8.5 1.5v4h4" />
+            <circle cx="8" cy="10.5" r="1.5" />
+        </svg>
+    }
+}
+
+pub fn split() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M1.5 2.5h13v11h-13z M8 2.5v11" />
+        </svg>
+    }
+}
+
+pub fn close() -> Html {
+    html! {
+        <svg class="icon" viewBox="0 0 16 16" fill="none" stroke="currentColor">
+            <path d="M4 4l8 8 M12 4l-8 8" />
+        </svg>
+    }
+}
+
+/// Returns the icon of a file based on its extension.
+pub fn file_type(file_name: &str) -> Html {
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "rs" | "c" | "h" | "cpp" | "hpp" | "cs" | "go" | "java" | "kt" | "js" | "jsx" | "ts"
+        | "tsx" | "py" | "rb" | "php" | "swift" | "sh" | "html" | "css" | "scss" => file_code(),
+        "md" | "txt" | "rst" | "adoc" => file_text(),
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "webp" | "ico" | "bmp" => file_image(),
+        "toml" | "json" | "yaml" | "yml" | "lock" | "ini" | "cfg" | "xml" => file_settings(),
+        _ => file(),
+    }
+}
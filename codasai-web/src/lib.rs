@@ -1,14 +1,35 @@
 use codasai_types::Guide;
 use wasm_bindgen::prelude::*;
 use web_sys::Element;
+use yew::ComponentLink;
 
 mod app;
 mod components;
+mod diffed_line;
 mod highlighted_chunk;
+mod icons;
+mod route;
+mod tabs;
 
 #[wasm_bindgen]
-pub fn start(guide: &str, el: Element) {
+pub fn start(guide: &str, el: Element) -> GuideHandle {
     let guide = Guide::from_json(guide).expect("a valid a guide encoded as json");
 
-    yew::App::<app::App>::new().mount_with_props(el, app::AppProps { guide });
+    let link = yew::App::<app::App>::new().mount_with_props(el, app::AppProps { guide });
+    GuideHandle(link)
+}
+
+/// Handle to a running guide app.
+#[wasm_bindgen]
+pub struct GuideHandle(ComponentLink<app::App>);
+
+#[wasm_bindgen]
+impl GuideHandle {
+    /// Replaces the guide shown by the app, keeping the reader on the same page
+    /// and file if they still exist.
+    pub fn reload(&self, guide: &str) {
+        let guide = Guide::from_json(guide).expect("a valid a guide encoded as json");
+
+        self.0.send_message(app::AppMessage::ReloadGuide(guide));
+    }
 }
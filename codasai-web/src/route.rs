@@ -0,0 +1,183 @@
+use std::ops::RangeInclusive;
+
+use codasai_types::VfsPath;
+
+/// A location in the guide that can be shared as a URL.
+///
+/// The syntax is `/page/$page/$path:$line_start-$line_end`, where the page is
+/// either the id of a snapshot or its number starting at 1, the lines start at
+/// 1 and both the path and the lines are optional.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Route {
+    pub page: String,
+    pub file_path: Option<VfsPath>,
+    pub lines: Option<RangeInclusive<usize>>,
+}
+
+impl Route {
+    /// Returns the route in the hash of the current URL or, if there is none,
+    /// in its path.
+    pub fn current() -> Option<Route> {
+        let location = yew::utils::window().location();
+        let hash = location.hash().ok()?;
+        let route = if hash.len() > 1 {
+            hash[1..].to_string()
+        } else {
+            location.pathname().ok()?
+        };
+
+        Self::parse(&String::from(js_sys::decode_uri(&route).ok()?))
+    }
+
+    pub fn parse(route: &str) -> Option<Route> {
+        let route = route.strip_prefix("/page/")?;
+        let (page, file) = match route.split_once('/') {
+            Some((page, file)) => (page, Some(file)),
+            None => (route, None),
+        };
+        if page.is_empty() {
+            return None;
+        }
+
+        // Invalid lines are ignored rather than read as part of the path, so the
+        // file still opens
+        let (file, lines) = match file.and_then(|file| file.rsplit_once(':')) {
+            Some((file, lines)) if is_line_range(lines) => (Some(file), parse_lines(lines)),
+            _ => (file, None),
+        };
+
+        let file_path = match file {
+            Some(file) if !file.is_empty() => Some(VfsPath::new(file).ok()?),
+            _ => None,
+        };
+
+        Some(Self {
+            page: page.to_string(),
+            file_path,
+            lines,
+        })
+    }
+
+    /// Replaces the hash of the current URL with this route. The browser adds
+    /// an entry to the history only if the route changed.
+    pub fn push(&self) {
+        let hash = format!("#{}", String::from(js_sys::encode_uri(&self.to_string())));
+        let location = yew::utils::window().location();
+
+        if location.hash().ok().as_deref() != Some(hash.as_str()) {
+            let _ = location.set_hash(&hash);
+        }
+    }
+}
+
+impl std::fmt::Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/page/{}", self.page)?;
+
+        if let Some(file_path) = &self.file_path {
+            write!(f, "/{}", file_path)?;
+
+            match &self.lines {
+                Some(lines) if lines.start() == lines.end() => write!(f, ":{}", lines.start())?,
+                Some(lines) => write!(f, ":{}-{}", lines.start(), lines.end())?,
+                None => {},
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns whether `lines` is written like `$line` or `$line_start-$line_end`,
+/// even if the lines are not valid.
+fn is_line_range(lines: &str) -> bool {
+    !lines.is_empty() && lines.chars().all(|c| c.is_ascii_digit() || c == '-')
+}
+
+/// Parses `$line` or `$line_start-$line_end`.
+fn parse_lines(lines: &str) -> Option<RangeInclusive<usize>> {
+    let (start, end) = lines.split_once('-').unwrap_or((lines, lines));
+    let start = start.parse().ok()?;
+    let end = end.parse().ok()?;
+
+    if start == 0 || end < start {
+        return None;
+    }
+
+    Some(start..=end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(page: &str, file_path: Option<&str>, lines: Option<RangeInclusive<usize>>) -> Route {
+        Route {
+            page: page.to_string(),
+            file_path: file_path.map(|path| VfsPath::new(path).unwrap()),
+            lines,
+        }
+    }
+
+    #[test]
+    fn test_display_and_parse_round_trip() {
+        let routes = vec![
+            route("intro", None, None),
+            route("3", None, None),
+            route("intro", Some("src/main.rs"), None),
+            route("intro", Some("src/main.rs"), Some(4..=4)),
+            route("intro", Some("src/main.rs"), Some(2..=7)),
+        ];
+
+        for expected in routes {
+            assert_eq!(Route::parse(&expected.to_string()), Some(expected));
+        }
+        assert_eq!(
+            route("intro", Some("src/main.rs"), Some(2..=7)).to_string(),
+            "/page/intro/src/main.rs:2-7"
+        );
+    }
+
+    #[test]
+    fn test_invalid_lines_are_ignored() {
+        for path in &[
+            "main.rs:0",
+            "main.rs:0-3",
+            "main.rs:5-2",
+            "main.rs:-",
+            "main.rs:1-2-3",
+        ] {
+            assert_eq!(
+                Route::parse(&format!("/page/intro/{}", path)),
+                Some(route("intro", Some("main.rs"), None)),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_page_id() {
+        assert_eq!(Route::parse("/page/"), None);
+        assert_eq!(Route::parse("/page//main.rs"), None);
+        assert_eq!(Route::parse("/page"), None);
+        assert_eq!(Route::parse("/intro/main.rs"), None);
+    }
+
+    #[test]
+    fn test_trailing_garbage() {
+        // A file can have a `:` in its name
+        assert_eq!(
+            Route::parse("/page/intro/main.rs:3-4abc"),
+            Some(route("intro", Some("main.rs:3-4abc"), None))
+        );
+        assert_eq!(
+            Route::parse("/page/intro/"),
+            Some(route("intro", None, None))
+        );
+        assert_eq!(
+            Route::parse("/page/intro/main.rs:"),
+            Some(route("intro", Some("main.rs:"), None))
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use codasai_types::VfsPath;
+
+/// The tabs of the editor: the open files, the active one and the one shown
+/// next to it. They are kept across pages, so the file shown by default when
+/// none was opened, which depends on the page, is given to the methods that
+/// need it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tabs {
+    /// The file of the active tab, unless the default one is shown.
+    active: Option<VfsPath>,
+    /// The open files, in the order of their tabs.
+    open: Vec<VfsPath>,
+    /// The file shown next to the active tab.
+    split: Option<VfsPath>,
+    /// Whether all the tabs were closed, in which case no file is shown until
+    /// one is opened.
+    all_closed: bool,
+}
+
+impl Tabs {
+    /// Returns the file of the active tab if one was opened.
+    pub fn opened_file(&self) -> Option<&VfsPath> {
+        self.active.as_ref()
+    }
+
+    pub fn split_file(&self) -> Option<&VfsPath> {
+        self.split.as_ref()
+    }
+
+    /// Returns the file shown in the editor, which is `default_file` if none
+    /// was opened and the tabs were not all closed.
+    pub fn active_file(&self, default_file: Option<VfsPath>) -> Option<VfsPath> {
+        if self.all_closed {
+            return self.active.clone();
+        }
+
+        self.active.clone().or(default_file)
+    }
+
+    /// Returns the files shown as tabs, which include the file shown by default
+    /// when none was opened.
+    pub fn visible(&self, default_file: Option<VfsPath>) -> Vec<VfsPath> {
+        let mut tabs = self.open.clone();
+        if let Some(active_file) = self.active_file(default_file) {
+            if !tabs.contains(&active_file) {
+                tabs.insert(0, active_file);
+            }
+        }
+
+        tabs
+    }
+
+    /// Adds a tab for the file if it isn't open and makes it the active one.
+    pub fn open(&mut self, path: VfsPath) {
+        if !self.open.contains(&path) {
+            self.open.push(path.clone());
+        }
+        self.active = Some(path);
+        self.all_closed = false;
+    }
+
+    /// Adds a tab for the file if it isn't open and shows it next to the
+    /// active one.
+    pub fn open_in_split(&mut self, path: VfsPath) {
+        if !self.open.contains(&path) {
+            self.open.push(path.clone());
+        }
+        self.split = Some(path);
+    }
+
+    pub fn close_split(&mut self) {
+        self.split = None;
+    }
+
+    /// Shows the default file instead of the active one, keeping the tabs.
+    pub fn show_default(&mut self) {
+        self.active = None;
+    }
+
+    /// Closes the tab of the file and returns whether it was the active one.
+    /// If it was, the tab after it, or else the one before it, becomes active.
+    pub fn close(&mut self, path: &VfsPath, default_file: Option<VfsPath>) -> bool {
+        let active = self.active_file(default_file).as_ref() == Some(path);
+        // The tab of the file shown by default isn't part of `open`
+        let index = self.open.iter().position(|tab| tab == path);
+        match index {
+            Some(index) => {
+                self.open.remove(index);
+            },
+            None if active => {},
+            None => return false,
+        }
+
+        if self.split.as_ref() == Some(path) {
+            self.split = None;
+        }
+        if active {
+            let index = index.unwrap_or(0).min(self.open.len().saturating_sub(1));
+            self.active = self.open.get(index).cloned();
+            self.all_closed = self.open.is_empty();
+            // The same file isn't shown twice
+            if self.split == self.active {
+                self.split = None;
+            }
+        }
+
+        active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(path: &str) -> VfsPath {
+        VfsPath::new(path).unwrap()
+    }
+
+    #[test]
+    fn test_default_file_is_shown_until_a_file_is_opened() {
+        let mut tabs = Tabs::default();
+
+        assert_eq!(
+            tabs.active_file(Some(path("main.rs"))),
+            Some(path("main.rs"))
+        );
+        assert_eq!(tabs.visible(Some(path("main.rs"))), vec![path("main.rs")]);
+
+        tabs.open(path("lib.rs"));
+
+        assert_eq!(
+            tabs.active_file(Some(path("main.rs"))),
+            Some(path("lib.rs"))
+        );
+        assert_eq!(tabs.visible(Some(path("main.rs"))), vec![path("lib.rs")]);
+    }
+
+    #[test]
+    fn test_close_last_tab() {
+        let mut tabs = Tabs::default();
+        tabs.open(path("lib.rs"));
+
+        assert!(tabs.close(&path("lib.rs"), Some(path("main.rs"))));
+        assert_eq!(tabs.active_file(Some(path("main.rs"))), None);
+        assert_eq!(tabs.visible(Some(path("main.rs"))), vec![]);
+    }
+
+    #[test]
+    fn test_close_default_tab() {
+        let mut tabs = Tabs::default();
+
+        assert!(tabs.close(&path("main.rs"), Some(path("main.rs"))));
+        assert_eq!(tabs.active_file(Some(path("main.rs"))), None);
+        assert_eq!(tabs.visible(Some(path("main.rs"))), vec![]);
+    }
+
+    #[test]
+    fn test_close_active_tab_in_split() {
+        let mut tabs = Tabs::default();
+        tabs.open(path("a.rs"));
+        tabs.open(path("b.rs"));
+        tabs.open(path("c.rs"));
+        tabs.open_in_split(path("a.rs"));
+        tabs.open(path("b.rs"));
+
+        // The tab after it becomes active and the split is kept
+        assert!(tabs.close(&path("b.rs"), None));
+        assert_eq!(tabs.active_file(None), Some(path("c.rs")));
+        assert_eq!(tabs.split_file(), Some(&path("a.rs")));
+        assert_eq!(tabs.visible(None), vec![path("a.rs"), path("c.rs")]);
+
+        // The tab before it is the split one, which isn't shown twice
+        assert!(tabs.close(&path("c.rs"), None));
+        assert_eq!(tabs.active_file(None), Some(path("a.rs")));
+        assert_eq!(tabs.split_file(), None);
+    }
+
+    #[test]
+    fn test_close_split_tab() {
+        let mut tabs = Tabs::default();
+        tabs.open(path("a.rs"));
+        tabs.open_in_split(path("b.rs"));
+
+        assert!(!tabs.close(&path("b.rs"), None));
+        assert_eq!(tabs.active_file(None), Some(path("a.rs")));
+        assert_eq!(tabs.split_file(), None);
+        assert_eq!(tabs.visible(None), vec![path("a.rs")]);
+    }
+
+    #[test]
+    fn test_reopen_closed_tab() {
+        let mut tabs = Tabs::default();
+        tabs.open(path("lib.rs"));
+        tabs.close(&path("lib.rs"), Some(path("main.rs")));
+
+        tabs.open(path("lib.rs"));
+
+        assert_eq!(
+            tabs.active_file(Some(path("main.rs"))),
+            Some(path("lib.rs"))
+        );
+        assert_eq!(tabs.visible(Some(path("main.rs"))), vec![path("lib.rs")]);
+
+        // Once a tab is open again, closing another one falls back to it
+        tabs.open(path("main.rs"));
+        tabs.close(&path("main.rs"), None);
+
+        assert_eq!(tabs.active_file(None), Some(path("lib.rs")));
+    }
+}